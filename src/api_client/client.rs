@@ -6,7 +6,7 @@ use serde::Serialize;
 use super::errors::ApiClientError;
 use super::schemas;
 use super::types::{ApiResult, ResponseBody};
-use super::utils::get_url;
+use super::utils::{deserialize_body, get_url};
 use crate::schemas::ExecLog;
 
 const API_HOST: &str = "http://localhost:8000";
@@ -16,27 +16,62 @@ pub struct ApiClient {
     pub client: blocking::Client,
 }
 
-impl ApiClient {
-    pub fn new(username: &str, password: &str) -> ApiResult<ApiClient> {
-        let api_host = match env::var("CHILISEED_API_HOST") {
-            Ok(val) => val,
-            Err(_err) => {
-                warn!("Falling back to default api host");
-                API_HOST.to_string()
+/// Resolve chiliseed API host. Host set explicitly in the profile takes
+/// precedence over `CHILISEED_API_HOST` environment variable, falling back
+/// to the default host.
+pub fn get_api_host(host: Option<&str>) -> String {
+    match (host, env::var("CHILISEED_API_HOST")) {
+        (Some(h), Ok(val)) => {
+            if val != h {
+                eprintln!(
+                    "Warning: using api host {} of the profile, ignoring CHILISEED_API_HOST",
+                    h
+                );
             }
-        };
+            h.to_string()
+        }
+        (Some(h), Err(_err)) => h.to_string(),
+        (None, Ok(val)) => val,
+        (None, Err(_err)) => {
+            warn!("Falling back to default api host");
+            API_HOST.to_string()
+        }
+    }
+}
+
+impl ApiClient {
+    /// Authenticate with provided credentials and construct client using
+    /// the returned auth token.
+    pub fn new(api_host: &str, username: &str, password: &str) -> ApiResult<ApiClient> {
+        let auth_token = ApiClient::login(api_host, username, password)?;
+        ApiClient::with_token(api_host, &auth_token)
+    }
 
-        let mut headers = header::HeaderMap::new();
+    /// Construct client from previously obtained auth token.
+    pub fn with_token(api_host: &str, auth_token: &str) -> ApiResult<ApiClient> {
+        let mut headers = get_default_headers();
         headers.insert(
-            header::CONTENT_TYPE,
-            header::HeaderValue::from_static("application/json"),
+            header::AUTHORIZATION,
+            header::HeaderValue::from_str(&format!("Token {}", auth_token)).unwrap(),
         );
+        let api_client = blocking::ClientBuilder::new()
+            .cookie_store(true)
+            .default_headers(headers)
+            .build()?;
+
+        Ok(ApiClient {
+            api_host: api_host.to_string(),
+            client: api_client,
+        })
+    }
 
+    /// Exchange username and password for an auth token.
+    pub fn login(api_host: &str, username: &str, password: &str) -> ApiResult<String> {
         let api_client = blocking::ClientBuilder::new()
-            .default_headers(headers.clone())
+            .default_headers(get_default_headers())
             .build()?;
 
-        let login_url = get_url(&api_host, "/api/auth/login")?;
+        let login_url = get_url(api_host, "/api/auth/login")?;
 
         debug!("Authenticating user");
 
@@ -66,20 +101,7 @@ impl ApiClient {
             );
         })?;
 
-        headers.insert(
-            header::AUTHORIZATION,
-            header::HeaderValue::from_str(&format!("Token {}", resp.auth_token)).unwrap(),
-        );
-        let api_client = blocking::ClientBuilder::new()
-            .cookie_store(true)
-            .default_headers(headers)
-            .build()
-            .unwrap();
-
-        Ok(ApiClient {
-            api_host: api_host.to_string(),
-            client: api_client,
-        })
+        Ok(resp.auth_token)
     }
 
     pub(crate) fn get(&self, endpoint: &str) -> ApiResult<(ResponseBody, StatusCode)> {
@@ -164,8 +186,17 @@ impl ApiClient {
     }
 
     pub fn get_exec_log(&self, slug: &str) -> ApiResult<ExecLog> {
-        let (response_body, _) = self.get(&format!("/api/execution/status/{}", slug))?;
-        let log: ExecLog = serde_json::from_str(&response_body).unwrap();
+        let (response_body, status) = self.get(&format!("/api/execution/status/{}", slug))?;
+        let log: ExecLog = deserialize_body(&response_body, status)?;
         Ok(log)
     }
 }
+
+fn get_default_headers() -> header::HeaderMap {
+    let mut headers = header::HeaderMap::new();
+    headers.insert(
+        header::CONTENT_TYPE,
+        header::HeaderValue::from_static("application/json"),
+    );
+    headers
+}
@@ -0,0 +1,23 @@
+use serde::Deserialize;
+
+use crate::api_client::types::ApiResult;
+use crate::api_client::utils::{deserialize_body, handle_empty_response_or_error};
+use crate::api_client::ApiClient;
+
+impl ApiClient {
+    pub fn get_current_user(&self) -> ApiResult<CurrentUserResponse> {
+        let (response, status) = self.get("/api/auth/me")?;
+        let user: CurrentUserResponse = deserialize_body(&response, status)?;
+        Ok(user)
+    }
+
+    pub fn logout(&self) -> ApiResult<()> {
+        let (response, status) = self.post("/api/auth/logout", None::<&()>)?;
+        handle_empty_response_or_error(&response, status)
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CurrentUserResponse {
+    pub email: String,
+}
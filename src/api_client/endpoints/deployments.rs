@@ -1,9 +1,10 @@
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 
 use crate::api_client::types::ApiResult;
 use crate::api_client::utils::deserialize_body;
 use crate::api_client::ApiClient;
-use crate::schemas::Worker;
+use crate::schemas::{Deployment, ExecLog, Worker};
 
 impl ApiClient {
     pub fn launch_worker(
@@ -25,6 +26,49 @@ impl ApiClient {
         Ok(worker)
     }
 
+    pub fn list_workers(&self) -> ApiResult<Vec<Worker>> {
+        let (response, status) = self.get("/api/worker/")?;
+        let workers: Vec<Worker> = deserialize_body(&response, status)?;
+        Ok(workers)
+    }
+
+    pub fn terminate_worker(&self, worker_slug: &str) -> ApiResult<ExecLog> {
+        let (response, status) = self.post(
+            &format!("/api/worker/{}/terminate", worker_slug),
+            None::<&()>,
+        )?;
+        let log: ExecLog = deserialize_body(&response, status)?;
+        Ok(log)
+    }
+
+    /// Short lived credentials for pushing images to the service's repository.
+    pub fn get_registry_credentials(&self, service_slug: &str) -> ApiResult<RegistryCredentials> {
+        let (response, status) = self.get(&format!(
+            "/api/service/{}/registry-credentials",
+            service_slug
+        ))?;
+        let credentials: RegistryCredentials = deserialize_body(&response, status)?;
+        Ok(credentials)
+    }
+
+    pub fn list_images(
+        &self,
+        service_slug: &str,
+        filters: &ImageListFilters,
+    ) -> ApiResult<Vec<ServiceImage>> {
+        let (response, status) =
+            self.get_with_query_params(&format!("/api/service/{}/images/", service_slug), filters)?;
+        let images: Vec<ServiceImage> = deserialize_body(&response, status)?;
+        Ok(images)
+    }
+
+    pub fn list_deployments(&self, service_slug: &str) -> ApiResult<Vec<Deployment>> {
+        let (response, status) =
+            self.get(&format!("/api/service/{}/deployments/", service_slug))?;
+        let deployments: Vec<Deployment> = deserialize_body(&response, status)?;
+        Ok(deployments)
+    }
+
     pub fn deploy_service(
         &self,
         service_slug: &str,
@@ -50,9 +94,35 @@ pub struct LaunchWorkerResponse {
     pub log: Option<String>,
 }
 
+#[derive(Debug, Deserialize)]
+pub struct RegistryCredentials {
+    pub username: String,
+    pub password: String,
+    pub registry: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ImageListFilters {
+    pub tag: Option<String>,
+}
+
+/// Image pushed to the service's ECR repository.
+#[derive(Debug, Deserialize)]
+pub struct ServiceImage {
+    pub tag: String,
+    pub pushed_at: DateTime<Utc>,
+}
+
 #[derive(Debug, Serialize)]
 pub struct ServiceDeployRequest {
     pub version: String,
+    /// Revision the image was built from, unknown for already built images
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub commit_sha: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub branch: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub commit_message: Option<String>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -68,7 +68,7 @@ pub struct CreateEnvironmentVariableResponse {
     pub key_name: String,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Serialize, Deserialize)]
 pub struct ListEnvironmentVariableResponse {
     pub name: String,
     pub value_from: String,
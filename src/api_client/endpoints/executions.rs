@@ -0,0 +1,47 @@
+use serde::{Deserialize, Serialize};
+
+use crate::api_client::types::ApiResult;
+use crate::api_client::utils::deserialize_body;
+use crate::api_client::ApiClient;
+use crate::schemas::ExecLog;
+
+impl ApiClient {
+    pub fn list_exec_logs(&self, filters: &ExecLogListFilters) -> ApiResult<Vec<ExecLog>> {
+        let (response_body, status) = self.get_with_query_params("/api/execution/", filters)?;
+        debug!("server response: {}", response_body);
+        let logs: Vec<ExecLog> = deserialize_body(&response_body, status)?;
+        Ok(logs)
+    }
+
+    /// Log lines of the execution, starting from `offset`. Returned offset
+    /// should be used to fetch the following lines.
+    pub fn get_exec_log_lines(&self, slug: &str, offset: u64) -> ApiResult<ExecLogLinesResponse> {
+        let (response, status) = self.get_with_query_params(
+            &format!("/api/execution/{}/logs", slug),
+            &ExecLogLinesFilter { offset },
+        )?;
+        let lines: ExecLogLinesResponse = deserialize_body(&response, status)?;
+        Ok(lines)
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct ExecLogListFilters {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub component: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub component_slug: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub action: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ExecLogLinesFilter {
+    pub offset: u64,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ExecLogLinesResponse {
+    pub lines: Vec<String>,
+    pub offset: u64,
+}
@@ -1,17 +1,21 @@
+mod auth;
 mod buckets;
 mod databases;
 mod deployments;
 mod env_vars;
 mod environments;
+mod executions;
 mod projects;
 mod resources;
 mod services;
 
+pub use self::auth::*;
 pub use self::buckets::*;
 pub use self::databases::*;
 pub use self::deployments::*;
 pub use self::env_vars::*;
 pub use self::environments::*;
+pub use self::executions::*;
 pub use self::projects::*;
 pub use self::resources::*;
 pub use self::services::*;
@@ -10,6 +10,7 @@ pub enum ApiClientError {
     HTTPTimeoutError(reqwest::Error),
     DeSerializerError(String),
     URLParseError(ParseError),
+    Unauthorized(String),
 }
 
 impl Error for ApiClientError {}
@@ -33,6 +34,7 @@ impl fmt::Display for ApiClientError {
             ApiClientError::HTTPTimeoutError(ref err) => err.fmt(f),
             ApiClientError::DeSerializerError(ref cause) => write!(f, "{}", cause),
             ApiClientError::URLParseError(ref err) => err.fmt(f),
+            ApiClientError::Unauthorized(ref cause) => write!(f, "{}", cause),
         }
     }
 }
@@ -6,6 +6,8 @@ use super::errors::ApiClientError;
 use super::schemas;
 use super::types::ApiResult;
 
+const UNAUTHORIZED_MESSAGE: &str = "Session has expired. Please login again.";
+
 pub(crate) fn get_url(base_url: &str, endpoint: &str) -> ApiResult<String> {
     let base = Url::parse(base_url)?;
     let url = base.join(endpoint)?;
@@ -20,7 +22,9 @@ where
     T: Deserialize<'de>,
 {
     serde_json::from_str(body).map_err(|err| {
-        if status.is_server_error() {
+        if status == StatusCode::UNAUTHORIZED {
+            ApiClientError::Unauthorized(UNAUTHORIZED_MESSAGE.to_string())
+        } else if status.is_server_error() {
             let api_err: Result<schemas::ApiError, _> = serde_json::from_str(body);
             match api_err {
                 Ok(api_err) => {
@@ -42,6 +46,10 @@ pub(crate) fn handle_empty_response_or_error(
 ) -> Result<(), ApiClientError> {
     if status.is_success() {
         Ok(())
+    } else if status == StatusCode::UNAUTHORIZED {
+        Err(ApiClientError::Unauthorized(
+            UNAUTHORIZED_MESSAGE.to_string(),
+        ))
     } else {
         match serde_json::from_str::<schemas::ApiError>(&response) {
             Ok(api_err) => {
@@ -0,0 +1,155 @@
+use std::process::exit;
+
+use text_io::read;
+
+use crate::api_client::{ApiClient, ApiClientError, CurrentUserResponse};
+use crate::config::ActiveProfile;
+use crate::credentials;
+use crate::credentials::Credentials;
+
+fn get_username(username: Option<String>) -> String {
+    username.unwrap_or_else(|| {
+        println!("Email: ");
+        read!()
+    })
+}
+
+fn get_password(password: Option<String>) -> String {
+    match password {
+        Some(p) => p,
+        None => match rpassword::read_password_from_tty(Some("Password: ")) {
+            Ok(p) => p,
+            Err(err) => {
+                eprintln!("Error reading password: {}", err);
+                exit(1);
+            }
+        },
+    }
+}
+
+/// Exchange credentials for auth token and store it for following commands.
+fn login_and_save(
+    profile: &ActiveProfile,
+    api_host: &str,
+    username: &str,
+    password: &str,
+) -> Option<ApiClient> {
+    let auth_token = match ApiClient::login(api_host, username, password) {
+        Ok(token) => token,
+        Err(err) => {
+            eprintln!("Error: {}", err);
+            return None;
+        }
+    };
+
+    if let Err(err) = credentials::save(
+        &profile.name,
+        &Credentials {
+            api_host: api_host.to_string(),
+            username: username.to_string(),
+            auth_token: auth_token.clone(),
+        },
+    ) {
+        eprintln!("Failed to save credentials: {}", err);
+    }
+
+    match ApiClient::with_token(api_host, &auth_token) {
+        Ok(client) => Some(client),
+        Err(err) => {
+            eprintln!("Error: {}", err);
+            None
+        }
+    }
+}
+
+pub fn login(profile: &ActiveProfile, username: Option<String>, password: Option<String>) {
+    let api_host = profile.api_host();
+    let username = get_username(username.or_else(|| profile.profile.user.clone()));
+    let password = get_password(password);
+    if login_and_save(profile, &api_host, &username, &password).is_some() {
+        println!("Logged in as {} ({})", username, profile.name);
+    }
+}
+
+pub fn logout(profile: &ActiveProfile) {
+    if let Some(creds) = credentials::load(&profile.name) {
+        match ApiClient::with_token(&creds.api_host, &creds.auth_token) {
+            Ok(client) => {
+                if let Err(err) = client.logout() {
+                    debug!("Failed to invalidate auth token: {}", err);
+                }
+            }
+            Err(err) => debug!("Error: {}", err),
+        }
+    }
+
+    match credentials::remove(&profile.name) {
+        Ok(true) => println!("Logged out"),
+        Ok(false) => println!("You are not logged in."),
+        Err(err) => eprintln!("Failed to remove credentials: {}", err),
+    }
+}
+
+pub fn whoami(api_client: &ApiClient) {
+    match api_client.get_current_user() {
+        Ok(CurrentUserResponse { email }) => println!("{}", email),
+        Err(err) => eprintln!("Error: {}", err),
+    }
+}
+
+/// Construct authenticated api client.
+/// Explicitly provided username and password take precedence, otherwise the
+/// auth token stored by `chiliseed login` is used. If the stored token was
+/// rejected by the server, asks for the password again.
+pub fn get_api_client_or_exit(
+    profile: &ActiveProfile,
+    username: Option<String>,
+    password: Option<String>,
+) -> ApiClient {
+    let api_host = profile.api_host();
+
+    if let (Some(username), Some(password)) = (&username, &password) {
+        return match ApiClient::new(&api_host, username, password) {
+            Ok(c) => c,
+            Err(err) => {
+                error!("{}", err);
+                exit(1)
+            }
+        };
+    }
+
+    let creds = match credentials::load(&profile.name) {
+        Some(creds) if creds.api_host == api_host => creds,
+        _ => {
+            eprintln!(
+                "You are not logged in to {}. Please run `chiliseed login` first.",
+                api_host
+            );
+            exit(1);
+        }
+    };
+
+    let api_client = match ApiClient::with_token(&api_host, &creds.auth_token) {
+        Ok(c) => c,
+        Err(err) => {
+            error!("{}", err);
+            exit(1)
+        }
+    };
+
+    match api_client.get_current_user() {
+        Ok(_) => api_client,
+        Err(ApiClientError::Unauthorized(reason)) => {
+            println!("{}", reason);
+            let password = get_password(password);
+            match login_and_save(profile, &api_host, &creds.username, &password) {
+                Some(c) => c,
+                None => exit(1),
+            }
+        }
+        Err(err) => {
+            error!("{}", err);
+            exit(1)
+        }
+    }
+}
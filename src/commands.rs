@@ -1,5 +1,11 @@
+use std::path::PathBuf;
+
 use structopt::StructOpt;
 
+use crate::context::Context;
+use crate::output::OutputFormat;
+use crate::services::VersionStrategy;
+
 /// Chiliseed command line interface
 /// AWS credentials are looked up first in environment variables with fallback
 /// to config files in current directory and then in user directory
@@ -12,16 +18,73 @@ use structopt::StructOpt;
 pub struct Opt {
     #[structopt(subcommand)]
     pub cmd: Command,
-    /// Chiliseed user email
+    /// Chiliseed user email, overrides credentials stored by `login` command
     #[structopt(short, long, env = "CHILISEED_USERNAME")]
-    pub username: String,
-    /// Chiliseed user password
+    pub username: Option<String>,
+    /// Chiliseed user password, overrides credentials stored by `login` command
     #[structopt(short, long, env = "CHILISEED_PASSWORD", hide_env_values = true)]
-    pub password: String,
+    pub password: Option<String>,
+    /// Name of the profile from ~/.config/chiliseed/config.toml to use
+    #[structopt(long, env = "CHILISEED_PROFILE")]
+    pub profile: Option<String>,
+    /// Output format of list commands
+    #[structopt(
+        short,
+        long,
+        env = "CHILISEED_OUTPUT",
+        default_value = "table",
+        possible_values = &OutputFormat::variants(),
+        case_insensitive = true
+    )]
+    pub output: OutputFormat,
+    /// Do not stream execution logs while waiting for infrastructure changes
+    #[structopt(short, long)]
+    pub quiet: bool,
+    /// Minutes to wait for infrastructure changes, overrides defaults of all commands
+    #[structopt(long, env = "CHILISEED_TIMEOUT")]
+    pub timeout: Option<u64>,
+    /// Seconds between checks of infrastructure changes status
+    #[structopt(long, env = "CHILISEED_POLL_INTERVAL")]
+    pub poll_interval: Option<u64>,
 }
 
 #[derive(Debug, StructOpt)]
 pub enum Command {
+    /// Authenticate and store auth token for following commands
+    #[structopt(name = "login")]
+    Login {},
+    /// Remove stored auth token
+    #[structopt(name = "logout")]
+    Logout {},
+    /// Show currently authenticated user
+    #[structopt(name = "whoami")]
+    WhoAmI {},
+    #[structopt(name = "profile", about = "Management commands for profiles")]
+    Profile {
+        #[structopt(subcommand)]
+        cmd: ProfileSubCommands,
+    },
+    #[structopt(
+        name = "context",
+        about = "Default environment, project and service for commands"
+    )]
+    Context {
+        #[structopt(subcommand)]
+        cmd: ContextSubCommands,
+    },
+    #[structopt(
+        name = "exec-log",
+        about = "Inspect past and running infrastructure executions"
+    )]
+    ExecLog {
+        #[structopt(subcommand)]
+        cmd: ExecLogSubCommands,
+    },
+    #[structopt(name = "worker", about = "Inspect and manage build workers")]
+    Worker {
+        #[structopt(subcommand)]
+        cmd: WorkerSubCommands,
+    },
     /// Environment sub commands
     #[structopt(name = "environment", about = "Management commands for environments")]
     Environment {
@@ -81,6 +144,199 @@ pub enum Command {
         #[structopt(subcommand)]
         cmd: DbSubCommands,
     },
+
+    /// Show changes needed to bring environment to the state of the manifest
+    #[structopt(name = "plan")]
+    Plan {
+        /// Path to the manifest file
+        #[structopt(short, long, default_value = "chiliseed.toml")]
+        file: String,
+    },
+
+    /// Create or update infrastructure to match the manifest
+    #[structopt(name = "apply")]
+    Apply {
+        /// Path to the manifest file
+        #[structopt(short, long, default_value = "chiliseed.toml")]
+        file: String,
+        /// Apply changes without asking for confirmation
+        #[structopt(long)]
+        auto_approve: bool,
+    },
+
+    /// Print manifest describing existing infrastructure of the environment
+    #[structopt(name = "export")]
+    Export {
+        /// Name of the environment to export
+        #[structopt(short, long = "environment", env = "CHILISEED_ENVIRONMENT")]
+        environment_name: Option<String>,
+    },
+}
+
+impl Command {
+    /// Fill in environment, project and service names that were not provided
+    /// in command line with defaults from the current context.
+    pub fn set_defaults(&mut self, defaults: &Context) {
+        match self {
+            Command::Project {
+                environment_name, ..
+            }
+            | Command::Export { environment_name } => {
+                set_default(environment_name, &defaults.environment);
+            }
+            Command::Service {
+                environment_name,
+                project_name,
+                cmd,
+            } => {
+                set_default(environment_name, &defaults.environment);
+                set_default(project_name, &defaults.project);
+                cmd.set_default_service(&defaults.service);
+            }
+            Command::EnvVar {
+                environment_name,
+                project_name,
+                service_name,
+                ..
+            } => {
+                set_default(environment_name, &defaults.environment);
+                set_default(project_name, &defaults.project);
+                set_default(service_name, &defaults.service);
+            }
+            Command::Db {
+                environment_name,
+                project_name,
+                ..
+            } => {
+                set_default(environment_name, &defaults.environment);
+                set_default(project_name, &defaults.project);
+            }
+            _ => {}
+        }
+    }
+}
+
+fn set_default(value: &mut Option<String>, default: &Option<String>) {
+    if value.is_none() {
+        *value = default.clone();
+    }
+}
+
+#[derive(Debug, StructOpt)]
+pub enum ProfileSubCommands {
+    /// List all profiles
+    List {},
+    /// Add new profile
+    Add {
+        /// Name of the profile. Example: staging
+        name: String,
+        /// Chiliseed API host. Example: https://api.chiliseed.com
+        #[structopt(long)]
+        host: Option<String>,
+        /// Chiliseed user email
+        #[structopt(long)]
+        user: Option<String>,
+        /// Default environment name for commands
+        #[structopt(long)]
+        environment: Option<String>,
+        /// Default project name for commands
+        #[structopt(long)]
+        project: Option<String>,
+    },
+    /// Remove profile and its stored credentials
+    Remove {
+        /// Name of the profile to remove
+        name: String,
+    },
+    /// Make profile the default for following commands
+    Use {
+        /// Name of the profile to switch to
+        name: String,
+    },
+}
+
+#[derive(Debug, StructOpt)]
+pub enum ContextSubCommands {
+    /// Set defaults for current directory, or for current user with --global
+    Set {
+        /// Default environment name. Example: prod
+        #[structopt(short, long)]
+        environment: Option<String>,
+        /// Default project name. Example: backend
+        #[structopt(short, long)]
+        project: Option<String>,
+        /// Default service name. Example: api
+        #[structopt(short, long)]
+        service: Option<String>,
+        /// Save for current user instead of current directory
+        #[structopt(long)]
+        global: bool,
+    },
+    /// Show defaults used by commands in current directory and where they come from
+    Show {},
+    /// Remove defaults of current directory, or of current user with --global
+    Clear {
+        /// Remove user context instead of current directory context
+        #[structopt(long)]
+        global: bool,
+    },
+}
+
+#[derive(Debug, StructOpt)]
+pub enum WorkerSubCommands {
+    /// List build workers
+    List {},
+    /// Show build worker details
+    Describe {
+        /// Worker slug, as shown by worker list
+        slug: String,
+    },
+    /// Open interactive shell on the build worker
+    Ssh {
+        /// Worker slug, as shown by worker list
+        slug: String,
+        /// Authenticate with keys of the local ssh-agent
+        #[structopt(long)]
+        ssh_agent: bool,
+        /// Verify worker host key with this known_hosts file, workers
+        /// verified by the API are added to it
+        #[structopt(long, parse(from_os_str))]
+        known_hosts: Option<PathBuf>,
+    },
+    /// Terminate the build worker
+    Terminate {
+        /// Worker slug, as shown by worker list
+        slug: String,
+        /// Terminate without asking for confirmation
+        #[structopt(long)]
+        auto_approve: bool,
+    },
+}
+
+#[derive(Debug, StructOpt)]
+pub enum ExecLogSubCommands {
+    /// List executions
+    List {
+        /// Only executions of this component kind. Example: service
+        #[structopt(long)]
+        component: Option<String>,
+        /// Only executions of the component with this slug
+        #[structopt(long)]
+        component_slug: Option<String>,
+        /// Only executions of this action. Example: create
+        #[structopt(long)]
+        action: Option<String>,
+    },
+    /// Show execution status and its logs
+    Show {
+        /// Execution slug, as printed when the execution started
+        slug: String,
+    },
+    /// Stream logs of a running execution and wait for it to finish
+    Wait {
+        /// Execution slug, as printed when the execution started
+        slug: String,
+    },
 }
 
 #[derive(Debug, StructOpt)]
@@ -109,36 +365,180 @@ pub enum ServiceSubCommands {
     /// List services for project in environment
     List {},
     /// Register new service and create the infrastructure
-    Create {},
-    /// Deploy new version of the service
+    Create {
+        #[structopt(flatten)]
+        params: ServiceParams,
+    },
+    /// Deploy new version of one or more services
     Deploy {
-        /// Name of the service to deploy
-        service_name: String,
-        /// Docker build arguments
+        /// Names of the services to deploy, deployed in parallel
+        service_names: Vec<String>,
+        /// Deploy all services of the project
+        #[structopt(long, conflicts_with = "service-names")]
+        all: bool,
+        #[structopt(flatten)]
+        params: DeployParams,
+    },
+    /// Create build package of the service and show its size, without deploying
+    Package {
+        /// Name of the service
+        service_name: Option<String>,
+        /// List packaged files with their sizes
+        #[structopt(long)]
+        list: bool,
+        /// Save the package to this file. Example: build.tar.gz
+        #[structopt(long)]
+        out: Option<String>,
+    },
+    /// List past deployments of the service
+    Deployments {
+        /// Name of the service
+        service_name: Option<String>,
+    },
+    /// Redeploy previous successful version of the service
+    Rollback {
+        /// Name of the service to roll back
+        service_name: Option<String>,
+        /// Version to roll back to, instead of the previous successful one
         #[structopt(long)]
-        build_arg: Option<Vec<String>>,
+        to: Option<String>,
     },
     /// Update service parameters
     Update {
         /// Name of the service to update
-        service_name: String,
+        service_name: Option<String>,
+        #[structopt(flatten)]
+        params: ServiceParams,
     },
     /// Add a bucket for your static files
     AddStatics {
         /// Name of the service to which you want to add a bucket for static files
-        service_name: String,
+        service_name: Option<String>,
     },
     /// Remove statics file bucket
-    RemoveStatics { service_name: String },
+    RemoveStatics { service_name: Option<String> },
     /// Add existing database to this service
     AddDb {
-        /// Name of the service to which you want to add a bucket for static files
-        service_name: String,
         /// Database identifier as shown by `db list` command
         identifier: String,
+        /// Name of the service to which you want to add the database
+        service_name: Option<String>,
     },
 }
 
+// Parameters of the service deploy command. Not a doc comment, as structopt
+// would use it as description of the command.
+#[derive(Debug, StructOpt)]
+pub struct DeployParams {
+    /// Docker build argument, repeat the option for each argument
+    #[structopt(long, number_of_values = 1)]
+    pub build_arg: Option<Vec<String>>,
+    /// Build and push the image with local docker instead of a build worker
+    #[structopt(long)]
+    pub local_build: bool,
+    /// Deploy already built image with this tag instead of building current revision
+    #[structopt(long, conflicts_with_all = &["local-build", "build-arg"])]
+    pub version: Option<String>,
+    /// Authenticate to the build worker with keys of the local ssh-agent
+    #[structopt(long, conflicts_with_all = &["local-build", "version"])]
+    pub ssh_agent: bool,
+    /// Verify build worker host key with this known_hosts file, workers
+    /// verified by the API are added to it
+    #[structopt(long, parse(from_os_str), conflicts_with_all = &["local-build", "version"])]
+    pub known_hosts: Option<PathBuf>,
+    /// After deploy wait until service health check answers with the new version
+    #[structopt(long)]
+    pub wait_healthy: bool,
+    /// Minutes to wait for the health check to pass
+    #[structopt(long, default_value = "10")]
+    pub health_timeout: u64,
+    /// Source of the version of new build, git-sha by default and
+    /// content-hash outside of git checkout
+    #[structopt(
+        long,
+        possible_values = &VersionStrategy::variants(),
+        case_insensitive = true,
+        conflicts_with = "version"
+    )]
+    pub version_from: Option<VersionStrategy>,
+    /// Deploy uncommitted changes, tagged as <version>-dirty-<hash>
+    #[structopt(long, conflicts_with = "version")]
+    pub allow_dirty: bool,
+    /// List files that would be uploaded and exit without deploying
+    #[structopt(long, conflicts_with = "version")]
+    pub dry_run: bool,
+}
+
+// Service parameters for create and update commands. Missing values are
+// asked for interactively, unless `--non-interactive` is set. Not a doc
+// comment, as structopt would use it as description of the commands.
+#[derive(Debug, StructOpt)]
+pub struct ServiceParams {
+    /// Service name. Example: api
+    #[structopt(long)]
+    pub name: Option<String>,
+    /// Service subdomain, for api.example.com subdomain is `api`
+    #[structopt(long)]
+    pub subdomain: Option<String>,
+    /// Port on which the container listens. Example: 8000
+    #[structopt(long)]
+    pub container_port: Option<u32>,
+    /// Port on which the load balancer listens for HTTP traffic. Example: 80
+    #[structopt(long)]
+    pub alb_http_port: Option<u32>,
+    /// Port on which the load balancer listens for HTTPS traffic. Example: 443
+    #[structopt(long)]
+    pub alb_https_port: Option<u32>,
+    /// Health check endpoint. Example: /api/health/check/
+    #[structopt(long)]
+    pub health_check: Option<String>,
+    /// Path to service's dockerfile, relative to project root
+    #[structopt(long)]
+    pub dockerfile: Option<String>,
+    /// Dockerfile target to build
+    #[structopt(long)]
+    pub target: Option<String>,
+    /// Service does not need web interface
+    #[structopt(long)]
+    pub no_web: bool,
+    /// Fail instead of asking for missing values
+    #[structopt(long)]
+    pub non_interactive: bool,
+}
+
+impl ServiceParams {
+    /// Whether any of the web interface parameters was provided.
+    pub fn has_web_params(&self) -> bool {
+        self.subdomain.is_some()
+            || self.container_port.is_some()
+            || self.alb_http_port.is_some()
+            || self.alb_https_port.is_some()
+            || self.health_check.is_some()
+    }
+}
+
+impl ServiceSubCommands {
+    fn set_default_service(&mut self, default: &Option<String>) {
+        match self {
+            ServiceSubCommands::Deploy {
+                service_names, all, ..
+            } if service_names.is_empty() && !*all => {
+                service_names.extend(default.clone());
+            }
+            ServiceSubCommands::Package { service_name, .. }
+            | ServiceSubCommands::Deployments { service_name }
+            | ServiceSubCommands::Rollback { service_name, .. }
+            | ServiceSubCommands::Update { service_name, .. }
+            | ServiceSubCommands::AddStatics { service_name }
+            | ServiceSubCommands::RemoveStatics { service_name }
+            | ServiceSubCommands::AddDb { service_name, .. } => {
+                set_default(service_name, default);
+            }
+            _ => {}
+        }
+    }
+}
+
 #[derive(Debug, StructOpt)]
 pub enum EnvVarSubCommands {
     /// Create new environment variable for a service
@@ -160,3 +560,42 @@ pub enum DbSubCommands {
     /// List all databases in project
     List {},
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse_deploy(args: &[&str]) -> (Vec<String>, DeployParams) {
+        let opt = Opt::from_iter(["chiliseed", "service", "deploy"].iter().chain(args));
+        match opt.cmd {
+            Command::Service {
+                cmd:
+                    ServiceSubCommands::Deploy {
+                        service_names,
+                        params,
+                        ..
+                    },
+                ..
+            } => (service_names, params),
+            cmd => panic!("unexpected command {:?}", cmd),
+        }
+    }
+
+    #[test]
+    fn build_arg_does_not_take_service_names() {
+        let (service_names, params) = parse_deploy(&["--build-arg", "A=1", "api", "worker"]);
+        assert_eq!(service_names, vec!["api", "worker"]);
+        assert_eq!(params.build_arg, Some(vec!["A=1".to_string()]));
+    }
+
+    #[test]
+    fn build_arg_is_repeated() {
+        let (service_names, params) =
+            parse_deploy(&["api", "--build-arg", "A=1", "--build-arg", "B=2"]);
+        assert_eq!(service_names, vec!["api"]);
+        assert_eq!(
+            params.build_arg,
+            Some(vec!["A=1".to_string(), "B=2".to_string()])
+        );
+    }
+}
@@ -0,0 +1,111 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+
+use serde::{Deserialize, Serialize};
+
+use crate::api_client::get_api_host;
+
+const CONFIG_DIR: &str = ".config/chiliseed";
+const CONFIG_FILE: &str = "config.toml";
+pub const DEFAULT_PROFILE: &str = "default";
+
+/// Named set of connection parameters and defaults for commands.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Profile {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub host: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub user: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub environment: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub project: Option<String>,
+}
+
+/// Content of `~/.config/chiliseed/config.toml`.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct Config {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub current_profile: Option<String>,
+    #[serde(default)]
+    pub profiles: BTreeMap<String, Profile>,
+}
+
+/// Profile selected for the current invocation.
+#[derive(Debug, Clone)]
+pub struct ActiveProfile {
+    pub name: String,
+    pub profile: Profile,
+}
+
+impl ActiveProfile {
+    pub fn api_host(&self) -> String {
+        get_api_host(self.profile.host.as_deref())
+    }
+}
+
+/// Directory holding chiliseed configuration files for current user.
+pub fn get_config_dir() -> io::Result<PathBuf> {
+    match dirs::home_dir() {
+        Some(home) => Ok(home.join(CONFIG_DIR)),
+        None => Err(io::Error::new(
+            io::ErrorKind::NotFound,
+            "Cannot find home directory",
+        )),
+    }
+}
+
+fn get_config_path() -> io::Result<PathBuf> {
+    Ok(get_config_dir()?.join(CONFIG_FILE))
+}
+
+impl Config {
+    /// Read user's config file. Missing file results in empty config.
+    pub fn load() -> io::Result<Config> {
+        let path = get_config_path()?;
+        if !path.exists() {
+            return Ok(Config::default());
+        }
+        let content = fs::read_to_string(&path)?;
+        toml::from_str(&content).map_err(|err| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("Malformed config file {}: {}", path.display(), err),
+            )
+        })
+    }
+
+    pub fn save(&self) -> io::Result<()> {
+        let path = get_config_path()?;
+        fs::create_dir_all(path.parent().unwrap())?;
+        let content = toml::to_string(self)
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err.to_string()))?;
+        fs::write(path, content)
+    }
+
+    /// Name of the profile to use when none was requested explicitly.
+    pub fn current_profile_name(&self) -> String {
+        self.current_profile
+            .clone()
+            .unwrap_or_else(|| DEFAULT_PROFILE.to_string())
+    }
+
+    /// Resolve profile by name, falling back to currently selected profile.
+    /// Default profile does not have to be present in config file.
+    pub fn get_profile(&self, name: Option<String>) -> Result<ActiveProfile, String> {
+        let name = name.unwrap_or_else(|| self.current_profile_name());
+        match self.profiles.get(&name) {
+            Some(profile) => Ok(ActiveProfile {
+                name,
+                profile: profile.clone(),
+            }),
+            None if name == DEFAULT_PROFILE => Ok(ActiveProfile {
+                name,
+                profile: Profile::default(),
+            }),
+            None => Err(format!("Profile {} does not exist", name)),
+        }
+    }
+}
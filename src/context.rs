@@ -0,0 +1,226 @@
+use std::env;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::config::{get_config_dir, ActiveProfile};
+use crate::utils::{add_row_to_output_table, get_output_table};
+
+const CONTEXT_FILE: &str = "context.toml";
+const DIRECTORY_CONTEXT_FILE: &str = ".chiliseed-context.toml";
+const ENVIRONMENT_VAR: &str = "CHILISEED_ENVIRONMENT";
+
+/// Default environment, project and service names for commands.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Context {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub environment: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub project: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub service: Option<String>,
+}
+
+impl Context {
+    /// Combine two contexts, values of `self` take precedence.
+    pub fn or(self, other: Context) -> Context {
+        Context {
+            environment: self.environment.or(other.environment),
+            project: self.project.or(other.project),
+            service: self.service.or(other.service),
+        }
+    }
+
+    fn read(path: &Path) -> io::Result<Context> {
+        let content = fs::read_to_string(path)?;
+        toml::from_str(&content).map_err(|err| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("Malformed context file {}: {}", path.display(), err),
+            )
+        })
+    }
+
+    fn write(&self, path: &Path) -> io::Result<()> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let content = toml::to_string(self)
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err.to_string()))?;
+        fs::write(path, content)
+    }
+}
+
+impl From<&ActiveProfile> for Context {
+    fn from(profile: &ActiveProfile) -> Context {
+        Context {
+            environment: profile.profile.environment.clone(),
+            project: profile.profile.project.clone(),
+            service: None,
+        }
+    }
+}
+
+fn get_user_context_path() -> io::Result<PathBuf> {
+    Ok(get_config_dir()?.join(CONTEXT_FILE))
+}
+
+/// Closest directory context file, looking from current directory upwards.
+fn find_directory_context_path() -> Option<PathBuf> {
+    let cwd = env::current_dir().ok()?;
+    cwd.ancestors()
+        .map(|dir| dir.join(DIRECTORY_CONTEXT_FILE))
+        .find(|path| path.is_file())
+}
+
+fn load_from(path: Option<PathBuf>) -> io::Result<Context> {
+    match path {
+        Some(p) if p.is_file() => Context::read(&p),
+        _ => Ok(Context::default()),
+    }
+}
+
+/// Context with description of where it comes from.
+struct ContextLayer {
+    source: String,
+    context: Context,
+}
+
+impl ContextLayer {
+    fn read(path: &Path) -> io::Result<ContextLayer> {
+        Ok(ContextLayer {
+            source: path.display().to_string(),
+            context: Context::read(path)?,
+        })
+    }
+}
+
+type ContextField = fn(&Context) -> &Option<String>;
+
+/// Contexts in order of precedence: directory context, then environment and
+/// project of the selected profile, then the user context.
+fn load_layers(profile: &ActiveProfile) -> io::Result<Vec<ContextLayer>> {
+    let mut layers = Vec::new();
+    if let Some(path) = find_directory_context_path() {
+        layers.push(ContextLayer::read(&path)?);
+    }
+    layers.push(ContextLayer {
+        source: format!("profile {}", profile.name),
+        context: Context::from(profile),
+    });
+    if let Some(path) = get_user_context_path().ok().filter(|p| p.is_file()) {
+        layers.push(ContextLayer::read(&path)?);
+    }
+    Ok(layers)
+}
+
+/// Load defaults for commands in current directory with the selected
+/// profile. Values given in command line take precedence over all of them.
+pub fn load(profile: &ActiveProfile) -> io::Result<Context> {
+    Ok(load_layers(profile)?
+        .into_iter()
+        .fold(Context::default(), |context, layer| {
+            context.or(layer.context)
+        }))
+}
+
+fn get_context_path(global: bool) -> io::Result<PathBuf> {
+    if global {
+        get_user_context_path()
+    } else {
+        Ok(find_directory_context_path().unwrap_or_else(|| PathBuf::from(DIRECTORY_CONTEXT_FILE)))
+    }
+}
+
+pub fn set_context(global: bool, context: Context) {
+    let path = match get_context_path(global) {
+        Ok(p) => p,
+        Err(err) => {
+            eprintln!("Error: {}", err);
+            return;
+        }
+    };
+    let current = match load_from(Some(path.clone())) {
+        Ok(c) => c,
+        Err(err) => {
+            eprintln!("Error: {}", err);
+            return;
+        }
+    };
+    match context.or(current).write(&path) {
+        Ok(()) => println!("Context saved to {}", path.display()),
+        Err(err) => eprintln!("Error saving context: {}", err),
+    }
+}
+
+pub fn clear_context(global: bool) {
+    let path = match get_context_path(global) {
+        Ok(p) => p,
+        Err(err) => {
+            eprintln!("Error: {}", err);
+            return;
+        }
+    };
+    if !path.is_file() {
+        println!("No context is set.");
+        return;
+    }
+    match fs::remove_file(&path) {
+        Ok(()) => println!("Removed context {}", path.display()),
+        Err(err) => eprintln!("Error removing context: {}", err),
+    }
+}
+
+/// Value of the field from the first context that has it, with its source.
+fn find_value(layers: &[ContextLayer], field: ContextField) -> (&str, &str) {
+    layers
+        .iter()
+        .find_map(|layer| {
+            field(&layer.context)
+                .as_deref()
+                .map(|value| (value, layer.source.as_str()))
+        })
+        .unwrap_or(("-", "-"))
+}
+
+pub fn show_context(profile: &ActiveProfile) {
+    let mut layers = match load_layers(profile) {
+        Ok(layers) => layers,
+        Err(err) => {
+            eprintln!("Error: {}", err);
+            return;
+        }
+    };
+    // environment variable is applied by commands together with flags
+    layers.insert(
+        0,
+        ContextLayer {
+            source: ENVIRONMENT_VAR.to_string(),
+            context: Context {
+                environment: env::var(ENVIRONMENT_VAR).ok(),
+                ..Context::default()
+            },
+        },
+    );
+
+    let mut table = get_output_table();
+    add_row_to_output_table(&mut table, vec!["Profile", &profile.name]);
+    let fields: [(&str, ContextField); 3] = [
+        ("Environment", |c| &c.environment),
+        ("Project", |c| &c.project),
+        ("Service", |c| &c.service),
+    ];
+    for (name, field) in fields.iter() {
+        let (value, source) = find_value(&layers, *field);
+        add_row_to_output_table(&mut table, vec![name, value, source]);
+    }
+    if let Some(path) = find_directory_context_path() {
+        add_row_to_output_table(
+            &mut table,
+            vec!["Directory context", &path.display().to_string()],
+        );
+    }
+    table.printstd();
+}
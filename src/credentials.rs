@@ -0,0 +1,91 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::fs::OpenOptions;
+use std::io;
+use std::io::Write;
+#[cfg(unix)]
+use std::os::unix::fs::{OpenOptionsExt, PermissionsExt};
+use std::path::PathBuf;
+
+use serde::{Deserialize, Serialize};
+
+use crate::config::get_config_dir;
+
+const CREDENTIALS_FILE: &str = "credentials";
+
+/// Auth token obtained by `chiliseed login`, stored in user's config directory
+/// per profile.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Credentials {
+    pub api_host: String,
+    pub username: String,
+    pub auth_token: String,
+}
+
+type CredentialsStore = BTreeMap<String, Credentials>;
+
+fn get_credentials_path() -> io::Result<PathBuf> {
+    Ok(get_config_dir()?.join(CREDENTIALS_FILE))
+}
+
+fn read_store() -> CredentialsStore {
+    let content = match get_credentials_path().and_then(fs::read_to_string) {
+        Ok(content) => content,
+        Err(_) => return CredentialsStore::new(),
+    };
+    match toml::from_str(&content) {
+        Ok(store) => store,
+        Err(err) => {
+            warn!("Ignoring malformed credentials file: {}", err);
+            CredentialsStore::new()
+        }
+    }
+}
+
+/// Store credentials in a file readable and writable only by the current user,
+/// on unix enforced with file mode.
+fn write_store(store: &CredentialsStore) -> io::Result<()> {
+    let path = get_credentials_path()?;
+    fs::create_dir_all(path.parent().unwrap())?;
+    let content = toml::to_string(store)
+        .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err.to_string()))?;
+
+    let mut options = OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    options.mode(0o600);
+    let mut creds_file = options.open(&path)?;
+    // mode is only applied on creation, tighten permissions of existing file
+    #[cfg(unix)]
+    {
+        let mut permissions = creds_file.metadata()?.permissions();
+        permissions.set_mode(0o600);
+        creds_file.set_permissions(permissions)?;
+    }
+    creds_file.write_all(content.as_bytes())?;
+    debug!("Credentials saved to {}", path.display());
+    Ok(())
+}
+
+/// Read stored credentials of the profile. Returns `None` if user has not
+/// logged in yet.
+pub fn load(profile: &str) -> Option<Credentials> {
+    read_store().remove(profile)
+}
+
+pub fn save(profile: &str, creds: &Credentials) -> io::Result<()> {
+    let mut store = read_store();
+    store.insert(profile.to_string(), creds.clone());
+    write_store(&store)
+}
+
+/// Remove stored credentials of the profile. Returns `false` if there was
+/// nothing to remove.
+pub fn remove(profile: &str) -> io::Result<bool> {
+    let mut store = read_store();
+    if store.remove(profile).is_none() {
+        return Ok(false);
+    }
+    write_store(&store)?;
+    Ok(true)
+}
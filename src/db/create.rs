@@ -3,11 +3,13 @@ use text_io::read;
 
 use crate::api_client::{ApiClient, CreateDbRequest};
 use crate::db::set_env_vars;
-use crate::env_vars::create_env_var_in_project;
 use crate::environments::get_env;
 use crate::projects::get_project;
 use crate::utils::await_exec_result;
 
+/// Databases take considerably longer to launch than other infra.
+pub const DB_TIMEOUT_MINUTES: u64 = 40;
+
 const DEV: &str = "dev";
 const PROD: &str = "prod";
 
@@ -86,7 +88,7 @@ pub fn create_db(api_client: &ApiClient, env_name: &str, project_name: &str) {
     };
 
     println!("Launching new db: {}", db_name);
-    let is_launched = await_exec_result(api_client, &run_slug, Some(40));
+    let is_launched = await_exec_result(api_client, &run_slug, Some(DB_TIMEOUT_MINUTES));
     if is_launched {
         // todo add verify that these env vars are not taken
         println!("Adding database environment variables");
@@ -99,6 +101,6 @@ pub fn create_db(api_client: &ApiClient, env_name: &str, project_name: &str) {
         };
         debug!("new db: {:?}", db);
 
-        set_env_vars(&db);
+        set_env_vars(api_client, &project, &db);
     }
 }
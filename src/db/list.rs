@@ -1,8 +1,9 @@
 use crate::api_client::{ApiClient, ResourceKind, ResourceListFilter};
+use crate::output::{print_structured, OutputFormat};
 use crate::schemas::Project;
 use crate::utils::{add_row_to_output_table, get_output_table};
 
-pub fn list_databases(api_client: &ApiClient, project: &Project) {
+pub fn list_databases(api_client: &ApiClient, project: &Project, output: OutputFormat) {
     let filter = ResourceListFilter {
         kind: ResourceKind::Database,
         identifier: None,
@@ -10,6 +11,10 @@ pub fn list_databases(api_client: &ApiClient, project: &Project) {
     match api_client.list_resources(&project.slug, Some(&filter)) {
         Ok(dbs) => {
             debug!("resources: {:?}", dbs);
+            if output.is_structured() {
+                print_structured(output, &dbs);
+                return;
+            }
             for db in dbs {
                 println!();
                 println!("{}", db.name);
@@ -39,6 +44,18 @@ pub fn list_databases(api_client: &ApiClient, project: &Project) {
                     &mut table,
                     vec!["Created at", &db.created_at.to_rfc2822()],
                 );
+                if output.is_wide() {
+                    add_row_to_output_table(&mut table, vec!["Slug", db.slug.as_str()]);
+                    add_row_to_output_table(&mut table, vec!["Status", db.status.as_str()]);
+                    add_row_to_output_table(
+                        &mut table,
+                        vec!["Address", db.configuration.address.as_str()],
+                    );
+                    add_row_to_output_table(
+                        &mut table,
+                        vec!["Port", &format!("{}", db.configuration.port)],
+                    );
+                }
                 table.printstd();
             }
         }
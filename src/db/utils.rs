@@ -2,7 +2,22 @@ use std::process::exit;
 
 use crate::api_client::{ApiClient, ResourceKind, ResourceListFilter};
 use crate::env_vars::create_env_var_in_project;
-use crate::schemas::Resource;
+use crate::schemas::{Project, Resource};
+
+const DB_HOST_KEY: &str = "DB_HOST";
+const DB_PORT_KEY: &str = "DB_PORT";
+const DB_USERNAME_KEY: &str = "DB_USERNAME";
+const DB_PASSWORD_KEY: &str = "DB_PASSWORD";
+const DB_NAME_KEY: &str = "DB_NAME";
+
+/// Environment variables set in project for each of its databases.
+pub const DB_ENV_VAR_KEYS: [&str; 5] = [
+    DB_NAME_KEY,
+    DB_HOST_KEY,
+    DB_PORT_KEY,
+    DB_USERNAME_KEY,
+    DB_PASSWORD_KEY,
+];
 
 pub fn get_db(api_client: &ApiClient, project_slug: &str, db_identifier: String) -> Resource {
     let filters = ResourceListFilter {
@@ -25,35 +40,23 @@ pub fn get_db(api_client: &ApiClient, project_slug: &str, db_identifier: String)
     }
 }
 
-pub fn set_env_vars(db: &Resource) {
-    let db_host_key = "DB_HOST";
-    let db_port_key = "DB_PORT";
-    let db_username_key = "DB_USERNAME";
-    let db_password_key = "DB_PASSWORD";
-    let db_name_key = "DB_NAME";
-
+pub fn set_env_vars(api_client: &ApiClient, project: &Project, db: &Resource) {
     let keys = vec![
-        (db_host_key, db.configuration.address.clone()),
-        (db_port_key, format!("{}", db.configuration.port)),
-        (db_username_key, db.configuration.username.clone()),
-        (db_password_key, db.configuration.password.clone()),
-        (db_name_key, db.name.clone()),
+        (DB_HOST_KEY, db.configuration.address.clone()),
+        (DB_PORT_KEY, format!("{}", db.configuration.port)),
+        (DB_USERNAME_KEY, db.configuration.username.clone()),
+        (DB_PASSWORD_KEY, db.configuration.password.clone()),
+        (DB_NAME_KEY, db.name.clone()),
     ];
     for (key, val) in keys {
-        if !create_env_var_in_project(api_client, &project.slug.clone(), key, &val) {
+        if !create_env_var_in_project(api_client, &project.slug, key, &val) {
             return;
         }
     }
     println!("Database parameters will be injected into your containers under following keys: ");
-    for key in vec![
-        db_name_key,
-        db_host_key,
-        db_port_key,
-        db_username_key,
-        db_password_key,
-    ] {
+    for key in DB_ENV_VAR_KEYS.iter() {
         println!("{}", key);
     }
 
-    println!("Redeploy {} services to see these variables.", project_name);
+    println!("Redeploy {} services to see these variables.", project.name);
 }
@@ -1,6 +1,7 @@
 use crate::api_client::{
     ApiClient, CreateEnvironmentVariableRequest, DeleteEnvironmentVariableRequest,
 };
+use crate::output::{print_structured, OutputFormat};
 use crate::schemas::Service;
 use crate::utils::{add_row_to_output_table, get_output_table};
 
@@ -51,9 +52,13 @@ pub fn create_env_var_in_project(
     }
 }
 
-pub fn list(api_client: &ApiClient, service: Service) {
+pub fn list(api_client: &ApiClient, service: Service, output: OutputFormat) {
     match api_client.list_env_vars(&service.slug) {
         Ok(env_vars) => {
+            if output.is_structured() {
+                print_structured(output, &env_vars);
+                return;
+            }
             if env_vars.is_empty() {
                 println!("Service {} has no environment variables.", service.name);
                 return;
@@ -61,10 +66,23 @@ pub fn list(api_client: &ApiClient, service: Service) {
 
             let mut table = get_output_table();
             for env_var in env_vars {
-                add_row_to_output_table(
-                    &mut table,
-                    vec![&env_var.name, &env_var.value_from, &env_var.value],
-                );
+                if output.is_wide() {
+                    add_row_to_output_table(
+                        &mut table,
+                        vec![
+                            &env_var.name,
+                            &env_var.value_from,
+                            &env_var.value,
+                            &env_var.kind,
+                            &env_var.last_modified,
+                        ],
+                    );
+                } else {
+                    add_row_to_output_table(
+                        &mut table,
+                        vec![&env_var.name, &env_var.value_from, &env_var.value],
+                    );
+                }
             }
             table.printstd();
         }
@@ -9,6 +9,7 @@ use text_io::read;
 use tokio;
 
 use crate::api_client::{ApiClient, CreateEnvRequest, EnvListFilters};
+use crate::output::{print_structured, OutputFormat};
 use crate::schemas::Env;
 use crate::utils::{add_row_to_output_table, await_exec_result, get_output_table};
 
@@ -73,9 +74,13 @@ pub fn add(api_client: &ApiClient, name: Option<String>, domain: Option<String>)
     }
 }
 
-pub fn list_envs(api_client: &ApiClient) {
+pub fn list_envs(api_client: &ApiClient, output: OutputFormat) {
     match api_client.list_envs(None) {
         Ok(envs) => {
+            if output.is_structured() {
+                print_structured(output, &envs);
+                return;
+            }
             if envs.is_empty() {
                 println!("You have not created any environments yet.");
                 return;
@@ -97,6 +102,13 @@ pub fn list_envs(api_client: &ApiClient) {
                     &mut table,
                     vec!["Status", env.last_status.status.as_str()],
                 );
+                if output.is_wide() {
+                    add_row_to_output_table(&mut table, vec!["Slug", env.slug.as_str()]);
+                    add_row_to_output_table(
+                        &mut table,
+                        vec!["Updated at", env.updated_at.to_rfc2822().as_str()],
+                    );
+                }
                 table.printstd();
             }
         }
@@ -0,0 +1,100 @@
+use std::process::exit;
+
+use crate::api_client::{ApiClient, ExecLogListFilters};
+use crate::output::{print_structured, OutputFormat};
+use crate::schemas::ExecLog;
+use crate::utils::{
+    add_row_to_output_table, await_exec_result, get_output_table, print_all_log_lines,
+};
+
+fn get_status(exec_log: &ExecLog) -> &'static str {
+    match exec_log.is_success {
+        Some(true) => "success",
+        Some(false) => "failed",
+        None => "running",
+    }
+}
+
+fn get_ended_at(exec_log: &ExecLog) -> String {
+    exec_log
+        .ended_at
+        .map(|t| t.to_rfc2822())
+        .unwrap_or_else(|| "-".to_string())
+}
+
+pub fn list_exec_logs(api_client: &ApiClient, filters: ExecLogListFilters, output: OutputFormat) {
+    match api_client.list_exec_logs(&filters) {
+        Ok(logs) => {
+            if output.is_structured() {
+                print_structured(output, &logs);
+                return;
+            }
+            if logs.is_empty() {
+                println!("No executions found.");
+                return;
+            }
+
+            let mut table = get_output_table();
+            let mut header = vec!["Slug", "Action", "Component", "Status", "Ended at"];
+            if output.is_wide() {
+                header.push("Component slug");
+            }
+            add_row_to_output_table(&mut table, header);
+            for log in logs.iter() {
+                let ended_at = get_ended_at(log);
+                let mut row = vec![
+                    log.slug.as_str(),
+                    log.action.as_str(),
+                    log.component.as_str(),
+                    get_status(log),
+                    ended_at.as_str(),
+                ];
+                if output.is_wide() {
+                    row.push(log.component_slug.as_str());
+                }
+                add_row_to_output_table(&mut table, row);
+            }
+            table.printstd();
+        }
+        Err(err) => eprintln!("Error getting executions: {}", err),
+    }
+}
+
+pub fn show_exec_log(api_client: &ApiClient, slug: &str, output: OutputFormat) {
+    let exec_log = match api_client.get_exec_log(slug) {
+        Ok(log) => log,
+        Err(err) => {
+            eprintln!("Error getting execution: {}", err);
+            exit(1);
+        }
+    };
+    if output.is_structured() {
+        print_structured(output, &exec_log);
+        return;
+    }
+
+    let mut table = get_output_table();
+    add_row_to_output_table(&mut table, vec!["Slug", exec_log.slug.as_str()]);
+    add_row_to_output_table(&mut table, vec!["Action", exec_log.action.as_str()]);
+    add_row_to_output_table(&mut table, vec!["Component", exec_log.component.as_str()]);
+    add_row_to_output_table(
+        &mut table,
+        vec!["Component slug", exec_log.component_slug.as_str()],
+    );
+    add_row_to_output_table(&mut table, vec!["Status", get_status(&exec_log)]);
+    add_row_to_output_table(
+        &mut table,
+        vec!["Ended at", get_ended_at(&exec_log).as_str()],
+    );
+    table.printstd();
+
+    println!();
+    print_all_log_lines(api_client, slug, 0);
+}
+
+/// Reattach to a run, exits with non zero code if the run failed.
+pub fn wait_exec_log(api_client: &ApiClient, slug: &str) {
+    if !await_exec_result(api_client, slug, None) {
+        exit(1);
+    }
+}
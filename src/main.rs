@@ -1,21 +1,33 @@
 mod api_client;
+mod auth;
 mod commands;
+mod config;
+mod context;
+mod credentials;
 mod db;
 mod env_vars;
 mod environments;
+mod exec_logs;
+mod manifest;
+mod output;
+mod profiles;
 mod projects;
 mod schemas;
 mod services;
 mod utils;
-
-use std::process::exit;
+mod workers;
 
 #[macro_use]
 extern crate log;
 use structopt::StructOpt;
 
-use crate::commands::{DbSubCommands, EnvVarSubCommands};
-use api_client::ApiClient;
+use crate::api_client::ExecLogListFilters;
+use crate::commands::{
+    ContextSubCommands, DbSubCommands, EnvVarSubCommands, ExecLogSubCommands, ProfileSubCommands,
+    WorkerSubCommands,
+};
+use crate::config::Profile;
+use crate::context::Context;
 use commands::{Command, EnvSubCommands, Opt, ProjectSubCommands, ServiceSubCommands};
 
 fn main() {
@@ -23,21 +35,137 @@ fn main() {
         .expect("Cannot initialize the logger that was already initialized.");
 
     info!("Firing up chiliseed CLI");
-    let args = Opt::from_args();
+    let mut args = Opt::from_args();
+    utils::set_wait_options(utils::WaitOptions {
+        quiet: args.quiet,
+        timeout_minutes: args.timeout,
+        poll_interval_secs: args.poll_interval,
+    });
+    utils::handle_interrupts();
+
+    if let Command::Profile { cmd } = args.cmd {
+        match cmd {
+            ProfileSubCommands::List {} => profiles::list_profiles(),
+            ProfileSubCommands::Add {
+                name,
+                host,
+                user,
+                environment,
+                project,
+            } => profiles::add_profile(
+                name,
+                Profile {
+                    host,
+                    user,
+                    environment,
+                    project,
+                },
+            ),
+            ProfileSubCommands::Remove { name } => profiles::remove_profile(name),
+            ProfileSubCommands::Use { name } => profiles::use_profile(name),
+        }
+        return;
+    }
+
+    let profile = utils::get_profile_or_exit(args.profile.take());
+    debug!("Using profile: {:?}", profile);
+
+    if let Command::Context { cmd } = args.cmd {
+        match cmd {
+            ContextSubCommands::Set {
+                environment,
+                project,
+                service,
+                global,
+            } => context::set_context(
+                global,
+                Context {
+                    environment,
+                    project,
+                    service,
+                },
+            ),
+            ContextSubCommands::Show {} => context::show_context(&profile),
+            ContextSubCommands::Clear { global } => context::clear_context(global),
+        }
+        return;
+    }
+
+    let defaults = utils::get_context_or_exit(&profile);
+    debug!("Using defaults: {:?}", defaults);
+    args.cmd.set_defaults(&defaults);
 
-    let api_client = match ApiClient::new(&args.username, &args.password) {
-        Ok(c) => c,
-        Err(err) => {
-            error!("{}", err);
-            exit(1)
+    match args.cmd {
+        Command::Login {} => {
+            auth::login(&profile, args.username, args.password);
+            return;
+        }
+        Command::Logout {} => {
+            auth::logout(&profile);
+            return;
         }
-    };
+        _ => {}
+    }
+
+    let api_client = auth::get_api_client_or_exit(&profile, args.username, args.password);
 
     match args.cmd {
+        Command::Login {}
+        | Command::Logout {}
+        | Command::Profile { .. }
+        | Command::Context { .. } => {}
+
+        Command::WhoAmI {} => auth::whoami(&api_client),
+
+        Command::ExecLog { cmd } => match cmd {
+            ExecLogSubCommands::List {
+                component,
+                component_slug,
+                action,
+            } => {
+                info!("Getting executions");
+                exec_logs::list_exec_logs(
+                    &api_client,
+                    ExecLogListFilters {
+                        component,
+                        component_slug,
+                        action,
+                    },
+                    args.output,
+                );
+            }
+            ExecLogSubCommands::Show { slug } => {
+                exec_logs::show_exec_log(&api_client, &slug, args.output)
+            }
+            ExecLogSubCommands::Wait { slug } => exec_logs::wait_exec_log(&api_client, &slug),
+        },
+
+        Command::Worker { cmd } => match cmd {
+            WorkerSubCommands::List {} => workers::list_workers(&api_client, args.output),
+            WorkerSubCommands::Describe { slug } => {
+                workers::describe_worker(&api_client, &slug, args.output)
+            }
+            WorkerSubCommands::Ssh {
+                slug,
+                ssh_agent,
+                known_hosts,
+            } => workers::ssh_worker(
+                &api_client,
+                &slug,
+                &services::SshOptions {
+                    use_agent: ssh_agent,
+                    known_hosts,
+                },
+            ),
+            WorkerSubCommands::Terminate { slug, auto_approve } => {
+                workers::terminate_worker(&api_client, &slug, auto_approve)
+            }
+        },
+
         Command::Environment { cmd } => match cmd {
             EnvSubCommands::List {} => {
                 info!("Getting your environments");
-                environments::list_envs(&api_client);
+                environments::list_envs(&api_client, args.output);
             }
 
             EnvSubCommands::Create { name, domain } => {
@@ -53,7 +181,7 @@ fn main() {
             ProjectSubCommands::List {} => {
                 info!("Getting list of project");
                 let env_name = projects::get_env_name(environment_name);
-                projects::list_projects(&api_client, &env_name);
+                projects::list_projects(&api_client, &env_name, args.output);
             }
 
             ProjectSubCommands::Create { name } => {
@@ -72,42 +200,75 @@ fn main() {
                 info!("Getting services for project");
                 let env = utils::get_environment_or_exit(&api_client, environment_name);
                 let project = utils::get_project_or_exit(&api_client, project_name, &env.slug);
-                services::list_services(&api_client, project);
+                services::list_services(&api_client, project, args.output);
             }
 
-            ServiceSubCommands::Create {} => {
+            ServiceSubCommands::Create { params } => {
                 let env_name = projects::get_env_name(environment_name);
                 let project_name = projects::get_project_name(project_name);
                 info!(
                     "Creating service for project: {}({})",
                     project_name, env_name
                 );
-                services::create_service(&api_client, &env_name, &project_name);
+                services::create_service(&api_client, &env_name, &project_name, params);
             }
 
-            ServiceSubCommands::Update { service_name } => {
+            ServiceSubCommands::Update {
+                service_name,
+                params,
+            } => {
                 let env = utils::get_environment_or_exit(&api_client, environment_name);
                 let project = utils::get_project_or_exit(&api_client, project_name, &env.slug);
-                let service = services::get_service(&api_client, &project, &service_name);
+                let service = utils::get_service_or_exit(&api_client, &project, service_name);
                 info!("Updating service: {}", service.name);
-                services::update(&api_client, project, service);
+                services::update(&api_client, project, service, params);
             }
 
             ServiceSubCommands::Deploy {
+                service_names,
+                all,
+                params,
+            } => {
+                let env = utils::get_environment_or_exit(&api_client, environment_name);
+                let project = utils::get_project_or_exit(&api_client, project_name, &env.slug);
+                let services =
+                    utils::get_services_or_exit(&api_client, &project, service_names, all);
+                info!("Deploying {} service(s)", services.len());
+                services::deploy(&api_client, &env, services, params);
+            }
+
+            ServiceSubCommands::Package {
                 service_name,
-                build_arg,
+                list,
+                out,
             } => {
                 let env = utils::get_environment_or_exit(&api_client, environment_name);
                 let project = utils::get_project_or_exit(&api_client, project_name, &env.slug);
-                let service = services::get_service(&api_client, &project, &service_name);
-                info!("Deploying service: {}", service.name);
-                services::deploy(&api_client, service, build_arg);
+                let service = utils::get_service_or_exit(&api_client, &project, service_name);
+                info!("Packaging service: {}", service.name);
+                services::package(&[service], list, out);
+            }
+
+            ServiceSubCommands::Deployments { service_name } => {
+                let env = utils::get_environment_or_exit(&api_client, environment_name);
+                let project = utils::get_project_or_exit(&api_client, project_name, &env.slug);
+                let service = utils::get_service_or_exit(&api_client, &project, service_name);
+                info!("Getting deployments of service: {}", service.name);
+                services::list_deployments(&api_client, service, args.output);
+            }
+
+            ServiceSubCommands::Rollback { service_name, to } => {
+                let env = utils::get_environment_or_exit(&api_client, environment_name);
+                let project = utils::get_project_or_exit(&api_client, project_name, &env.slug);
+                let service = utils::get_service_or_exit(&api_client, &project, service_name);
+                info!("Rolling back service: {}", service.name);
+                services::rollback(&api_client, service, to);
             }
 
             ServiceSubCommands::AddStatics { service_name } => {
                 let env = utils::get_environment_or_exit(&api_client, environment_name);
                 let project = utils::get_project_or_exit(&api_client, project_name, &env.slug);
-                let service = services::get_service(&api_client, &project, &service_name);
+                let service = utils::get_service_or_exit(&api_client, &project, service_name);
                 info!("Adding static files bucket to service: {}", service.name);
                 services::add_statics(&api_client, service, project);
             }
@@ -115,7 +276,7 @@ fn main() {
             ServiceSubCommands::RemoveStatics { service_name } => {
                 let env = utils::get_environment_or_exit(&api_client, environment_name);
                 let project = utils::get_project_or_exit(&api_client, project_name, &env.slug);
-                let service = services::get_service(&api_client, &project, &service_name);
+                let service = utils::get_service_or_exit(&api_client, &project, service_name);
                 info!("Removing static files bucket to service: {}", service.name);
                 services::remove_statics(&api_client, service);
             }
@@ -126,10 +287,10 @@ fn main() {
             } => {
                 let env = utils::get_environment_or_exit(&api_client, environment_name);
                 let project = utils::get_project_or_exit(&api_client, project_name, &env.slug);
-                let service = services::get_service(&api_client, &project, &service_name);
+                let service = utils::get_service_or_exit(&api_client, &project, service_name);
                 let db = utils::get_resource_or_exit(&api_client, &project, identifier);
                 info!("Adding db {} to service {}", db.identifier, service.name);
-                services::add_database(&api_client, service, db);
+                services::add_database(&api_client, &project, service, db);
             }
         },
 
@@ -155,7 +316,7 @@ fn main() {
                 let project = utils::get_project_or_exit(&api_client, project_name, &env.slug);
                 let service = utils::get_service_or_exit(&api_client, &project, service_name);
                 info!("Listing environment variables for service: {} in project: {} in environment: {}", service.name, project.name, env.name);
-                env_vars::list(&api_client, service);
+                env_vars::list(&api_client, service, args.output);
             }
         },
 
@@ -178,8 +339,24 @@ fn main() {
                     "Listing databases in project {} ({})",
                     project.name, env.name
                 );
-                db::list_databases(&api_client, &project);
+                db::list_databases(&api_client, &project, args.output);
             }
         },
+
+        Command::Plan { file } => {
+            info!("Planning changes for manifest {}", file);
+            manifest::plan(&api_client, &file);
+        }
+
+        Command::Apply { file, auto_approve } => {
+            info!("Applying manifest {}", file);
+            manifest::apply(&api_client, &file, auto_approve);
+        }
+
+        Command::Export { environment_name } => {
+            let env = utils::get_environment_or_exit(&api_client, environment_name);
+            info!("Exporting environment {}", env.name);
+            manifest::export(&api_client, &env);
+        }
     }
 }
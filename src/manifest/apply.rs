@@ -0,0 +1,242 @@
+use std::collections::HashMap;
+use std::process::exit;
+
+use super::plan::{compute_plan, Change};
+use super::types::{Manifest, ManifestError, ManifestResult};
+use crate::api_client::{ApiClient, ProjectRequest, ServiceListFilter};
+use crate::db::{set_env_vars, DB_TIMEOUT_MINUTES};
+use crate::env_vars;
+use crate::schemas::{Env, Project, Service};
+use crate::services::{add_statics, ask_yes_no};
+use crate::utils::await_exec_result;
+
+/// Projects and services of the environment, updated as changes are applied.
+struct State {
+    env: Env,
+    projects: HashMap<String, Project>,
+    services: HashMap<(String, String), Service>,
+}
+
+impl State {
+    fn get_project(&self, name: &str) -> ManifestResult<Project> {
+        self.projects
+            .get(name)
+            .cloned()
+            .ok_or_else(|| ManifestError::NotFound(format!("Project {} was not found", name)))
+    }
+
+    fn get_service(
+        &mut self,
+        api_client: &ApiClient,
+        project: &Project,
+        name: &str,
+    ) -> ManifestResult<Service> {
+        let key = (project.name.clone(), name.to_string());
+        if let Some(service) = self.services.get(&key) {
+            return Ok(service.clone());
+        }
+        let services = api_client.list_services(
+            &project.slug,
+            Some(&ServiceListFilter {
+                name: name.to_string(),
+            }),
+        )?;
+        match services.into_iter().next() {
+            Some(service) => {
+                self.services.insert(key, service.clone());
+                Ok(service)
+            }
+            None => Err(ManifestError::NotFound(format!(
+                "Service {} was not found",
+                name
+            ))),
+        }
+    }
+}
+
+fn await_or_fail(
+    api_client: &ApiClient,
+    run_slug: &str,
+    timeout: Option<u64>,
+) -> ManifestResult<()> {
+    if await_exec_result(api_client, run_slug, timeout) {
+        Ok(())
+    } else {
+        Err(ManifestError::ApplyError(format!(
+            "Execution {} did not finish successfully",
+            run_slug
+        )))
+    }
+}
+
+/// There is no update of environment variables, existing one is deleted and
+/// created again with the new value. When that fails the previous value is
+/// restored, so the variable is not left missing.
+fn replace_env_var(
+    api_client: &ApiClient,
+    service: Service,
+    key: &str,
+    value: &str,
+) -> ManifestResult<()> {
+    let previous = api_client
+        .list_env_vars(&service.slug)?
+        .into_iter()
+        .find(|e| e.name == key)
+        .map(|e| e.value);
+    if !env_vars::delete_env_var(api_client, &service.slug, key) {
+        return Err(ManifestError::ApplyError(format!(
+            "Failed to delete environment variable {} to replace it, it was not changed",
+            key
+        )));
+    }
+    if env_vars::create(api_client, service.clone(), key, value) {
+        return Ok(());
+    }
+    let restored = match previous {
+        Some(previous) => env_vars::create(api_client, service, key, &previous),
+        None => false,
+    };
+    Err(ManifestError::ApplyError(if restored {
+        format!(
+            "Failed to set new value of environment variable {}, previous value was restored",
+            key
+        )
+    } else {
+        format!(
+            "Environment variable {} was deleted but setting its new value failed, it is now missing from the service",
+            key
+        )
+    }))
+}
+
+fn apply_change(api_client: &ApiClient, state: &mut State, change: &Change) -> ManifestResult<()> {
+    match change {
+        Change::CreateProject { project } => {
+            let resp = api_client.create_project(
+                &ProjectRequest {
+                    name: project.clone(),
+                },
+                &state.env.slug,
+            )?;
+            await_or_fail(api_client, &resp.log, None)?;
+            state.projects.insert(project.clone(), resp.project);
+        }
+
+        Change::CreateService { project, service } => {
+            let project = state.get_project(project)?;
+            let resp = api_client.create_service(&service.to_create_request(), &project.slug)?;
+            await_or_fail(api_client, &resp.log, None)?;
+            state
+                .services
+                .insert((project.name.clone(), service.name.clone()), resp.service);
+        }
+
+        Change::UpdateService {
+            project, service, ..
+        } => {
+            let project = state.get_project(project)?;
+            let resp = api_client.update_service(service, &project.slug)?;
+            await_or_fail(api_client, &resp.log, None)?;
+            state
+                .services
+                .insert((project.name.clone(), service.name.clone()), resp.service);
+        }
+
+        Change::CreateDatabase { project, database } => {
+            let project = state.get_project(project)?;
+            let resp = api_client
+                .create_db(&state.env.slug, &database.to_create_request(&project.slug))?;
+            await_or_fail(api_client, &resp.log, Some(DB_TIMEOUT_MINUTES))?;
+            let db = api_client.get_resource_details(&resp.resource)?;
+            set_env_vars(api_client, &project, &db);
+        }
+
+        Change::AddStaticsBucket { project, service } => {
+            let project = state.get_project(project)?;
+            let service = state.get_service(api_client, &project, service)?;
+            if !add_statics(api_client, service, project) {
+                return Err(ManifestError::ApplyError(
+                    "Failed to add statics bucket".to_string(),
+                ));
+            }
+        }
+
+        Change::SetEnvVar {
+            project,
+            service,
+            key,
+            value,
+            exists,
+        } => {
+            let project = state.get_project(project)?;
+            let service = state.get_service(api_client, &project, service)?;
+            if *exists {
+                replace_env_var(api_client, service, key, value)?;
+            } else if !env_vars::create(api_client, service, key, value) {
+                return Err(ManifestError::ApplyError(format!(
+                    "Failed to create environment variable {}",
+                    key
+                )));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Create or update everything that differs between the manifest and the
+/// environment, waiting for each change to finish before the next one.
+pub fn apply(api_client: &ApiClient, manifest_path: &str, auto_approve: bool) {
+    let manifest = match Manifest::load(manifest_path) {
+        Ok(m) => m,
+        Err(err) => {
+            eprintln!("Error: {}", err);
+            exit(1);
+        }
+    };
+    let plan = match compute_plan(api_client, &manifest) {
+        Ok(p) => p,
+        Err(err) => {
+            eprintln!("Error: {}", err);
+            exit(1);
+        }
+    };
+    plan.print();
+    if plan.changes.is_empty() {
+        return;
+    }
+
+    if !auto_approve
+        && !ask_yes_no(
+            "Do you want to apply these changes? [y/n]: ".to_string(),
+            false,
+        )
+    {
+        println!("Apply cancelled.");
+        return;
+    }
+
+    let projects = match api_client.list_projects(&plan.env.slug, None) {
+        Ok(projects) => projects,
+        Err(err) => {
+            eprintln!("Error: {}", err);
+            exit(1);
+        }
+    };
+    let mut state = State {
+        env: plan.env.clone(),
+        projects: projects.into_iter().map(|p| (p.name.clone(), p)).collect(),
+        services: HashMap::new(),
+    };
+
+    for change in plan.changes.iter() {
+        println!();
+        println!("Applying: {}", change);
+        if let Err(err) = apply_change(api_client, &mut state, change) {
+            eprintln!("Error: {}", err);
+            eprintln!("Apply stopped, run `chiliseed plan` to see remaining changes.");
+            exit(1);
+        }
+    }
+    println!();
+    println!("Applied {} changes.", plan.changes.len());
+}
@@ -0,0 +1,133 @@
+use std::collections::BTreeMap;
+use std::process::exit;
+
+use super::types::{
+    placeholder, DatabaseManifest, EnvironmentManifest, Manifest, ManifestError, ManifestResult,
+    ProjectManifest, ServiceManifest,
+};
+use crate::api_client::{ApiClient, ResourceKind, ResourceListFilter};
+use crate::db::DB_ENV_VAR_KEYS;
+use crate::schemas::{Env, Project, Resource, Service};
+use crate::services::STATICS_BUCKET_KEY_NAME;
+
+/// Kind of SSM parameters holding secret values.
+const SECRET_KIND: &str = "SecureString";
+
+fn list_resources(
+    api_client: &ApiClient,
+    project: &Project,
+    kind: ResourceKind,
+) -> ManifestResult<Vec<Resource>> {
+    let resources = api_client.list_resources(
+        &project.slug,
+        Some(&ResourceListFilter {
+            kind,
+            identifier: None,
+        }),
+    )?;
+    Ok(resources)
+}
+
+fn export_service(
+    api_client: &ApiClient,
+    service: &Service,
+    buckets: &[Resource],
+) -> ManifestResult<ServiceManifest> {
+    let mut env_vars = BTreeMap::new();
+    for env_var in api_client.list_env_vars(&service.slug)? {
+        // these are managed by databases and statics buckets
+        if env_var.name == STATICS_BUCKET_KEY_NAME
+            || DB_ENV_VAR_KEYS.contains(&env_var.name.as_str())
+        {
+            continue;
+        }
+        let value = if env_var.kind == SECRET_KIND {
+            placeholder(&env_var.name)
+        } else {
+            env_var.value
+        };
+        env_vars.insert(env_var.name, value);
+    }
+
+    Ok(ServiceManifest {
+        name: service.name.clone(),
+        has_web_interface: service.has_web_interface,
+        subdomain: service.subdomain.clone(),
+        container_port: service.container_port,
+        alb_port_http: service.alb_port_http,
+        alb_port_https: service.alb_port_https,
+        health_check_endpoint: service.health_check_endpoint.clone(),
+        default_dockerfile_path: service.default_dockerfile_path.clone(),
+        default_dockerfile_target: service
+            .default_dockerfile_target
+            .clone()
+            .filter(|t| !t.is_empty()),
+        statics_bucket: buckets.iter().any(|b| b.name == service.name),
+        env_vars,
+    })
+}
+
+fn export_project(api_client: &ApiClient, project: &Project) -> ManifestResult<ProjectManifest> {
+    eprintln!("Exporting project {}", project.name);
+
+    let databases = list_resources(api_client, project, ResourceKind::Database)?
+        .into_iter()
+        .map(|db| DatabaseManifest {
+            name: db.name,
+            username: db.configuration.username,
+            engine: db.engine,
+            preset: db.preset,
+        })
+        .collect();
+
+    match list_resources(api_client, project, ResourceKind::Cache) {
+        Ok(caches) => {
+            for cache in caches {
+                eprintln!(
+                    "Skipping cache {}, caches are not supported by the manifest",
+                    cache.name
+                );
+            }
+        }
+        Err(err) => debug!("Error listing caches: {}", err),
+    }
+
+    let buckets = list_resources(api_client, project, ResourceKind::Bucket)?;
+    let mut services = Vec::new();
+    for service in api_client.list_services(&project.slug, None)? {
+        services.push(export_service(api_client, &service, &buckets)?);
+    }
+
+    Ok(ProjectManifest {
+        name: project.name.clone(),
+        services,
+        databases,
+    })
+}
+
+pub fn export_manifest(api_client: &ApiClient, env: &Env) -> ManifestResult<Manifest> {
+    let mut projects = Vec::new();
+    for project in api_client.list_projects(&env.slug, None)? {
+        projects.push(export_project(api_client, &project)?);
+    }
+    Ok(Manifest {
+        environment: EnvironmentManifest {
+            name: env.name.clone(),
+        },
+        projects,
+    })
+}
+
+/// Print manifest describing existing infrastructure of the environment.
+pub fn export(api_client: &ApiClient, env: &Env) {
+    let serialized = export_manifest(api_client, env).and_then(|manifest| {
+        toml::to_string(&manifest).map_err(|err| ManifestError::ParseError(err.to_string()))
+    });
+    match serialized {
+        Ok(manifest) => print!("{}", manifest),
+        Err(err) => {
+            eprintln!("Error: {}", err);
+            exit(1);
+        }
+    }
+}
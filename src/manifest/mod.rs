@@ -0,0 +1,8 @@
+mod apply;
+mod export;
+mod plan;
+mod types;
+
+pub use self::apply::*;
+pub use self::export::*;
+pub use self::plan::*;
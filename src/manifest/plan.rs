@@ -0,0 +1,271 @@
+use std::fmt;
+use std::process::exit;
+
+use super::types::{
+    resolve_value, DatabaseManifest, Manifest, ManifestError, ManifestResult, ProjectManifest,
+    ServiceManifest,
+};
+use crate::api_client::{ApiClient, ResourceKind, ResourceListFilter};
+use crate::environments::get_env;
+use crate::schemas::{Env, Project, Service};
+
+/// Single action required to bring environment to the state described in
+/// the manifest.
+#[derive(Debug, Clone)]
+pub enum Change {
+    CreateProject {
+        project: String,
+    },
+    CreateService {
+        project: String,
+        service: ServiceManifest,
+    },
+    UpdateService {
+        project: String,
+        service: Service,
+        changes: Vec<String>,
+    },
+    CreateDatabase {
+        project: String,
+        database: DatabaseManifest,
+    },
+    AddStaticsBucket {
+        project: String,
+        service: String,
+    },
+    SetEnvVar {
+        project: String,
+        service: String,
+        key: String,
+        value: String,
+        exists: bool,
+    },
+}
+
+impl Change {
+    pub fn is_create(&self) -> bool {
+        match self {
+            Change::UpdateService { .. } => false,
+            Change::SetEnvVar { exists, .. } => !exists,
+            _ => true,
+        }
+    }
+}
+
+impl fmt::Display for Change {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Change::CreateProject { project } => write!(f, "+ project {}", project),
+            Change::CreateService { project, service } => {
+                write!(f, "+ service {}/{}", project, service.name)
+            }
+            Change::UpdateService {
+                project,
+                service,
+                changes,
+            } => {
+                write!(f, "~ service {}/{}", project, service.name)?;
+                for change in changes {
+                    write!(f, "\n    {}", change)?;
+                }
+                Ok(())
+            }
+            Change::CreateDatabase { project, database } => {
+                write!(f, "+ database {}/{}", project, database.name)
+            }
+            Change::AddStaticsBucket { project, service } => {
+                write!(f, "+ statics bucket {}/{}", project, service)
+            }
+            Change::SetEnvVar {
+                project,
+                service,
+                key,
+                exists,
+                ..
+            } => {
+                let sign = if *exists { "~" } else { "+" };
+                write!(f, "{} env var {}/{} {}", sign, project, service, key)
+            }
+        }
+    }
+}
+
+pub struct Plan {
+    pub env: Env,
+    pub changes: Vec<Change>,
+}
+
+impl Plan {
+    pub fn print(&self) {
+        if self.changes.is_empty() {
+            println!(
+                "Environment {} is up to date with the manifest.",
+                self.env.name
+            );
+            return;
+        }
+        for change in self.changes.iter() {
+            println!("{}", change);
+        }
+        let to_create = self.changes.iter().filter(|c| c.is_create()).count();
+        println!();
+        println!(
+            "Plan: {} to create, {} to update.",
+            to_create,
+            self.changes.len() - to_create
+        );
+    }
+}
+
+/// Compare manifest with the current state of the environment.
+pub fn compute_plan(api_client: &ApiClient, manifest: &Manifest) -> ManifestResult<Plan> {
+    let env = get_env(api_client, &manifest.environment.name)
+        .map_err(|err| ManifestError::NotFound(err.to_string()))?;
+    let projects = api_client.list_projects(&env.slug, None)?;
+
+    let mut changes = Vec::new();
+    for project_manifest in manifest.projects.iter() {
+        match projects.iter().find(|p| p.name == project_manifest.name) {
+            Some(project) => {
+                plan_existing_project(api_client, project, project_manifest, &mut changes)?
+            }
+            None => plan_new_project(project_manifest, &mut changes)?,
+        }
+    }
+
+    Ok(Plan { env, changes })
+}
+
+fn plan_new_project(project: &ProjectManifest, changes: &mut Vec<Change>) -> ManifestResult<()> {
+    changes.push(Change::CreateProject {
+        project: project.name.clone(),
+    });
+    for database in project.databases.iter() {
+        changes.push(Change::CreateDatabase {
+            project: project.name.clone(),
+            database: database.clone(),
+        });
+    }
+    for service in project.services.iter() {
+        plan_new_service(&project.name, service, changes)?;
+    }
+    Ok(())
+}
+
+fn plan_new_service(
+    project: &str,
+    service: &ServiceManifest,
+    changes: &mut Vec<Change>,
+) -> ManifestResult<()> {
+    changes.push(Change::CreateService {
+        project: project.to_string(),
+        service: service.clone(),
+    });
+    if service.statics_bucket {
+        changes.push(Change::AddStaticsBucket {
+            project: project.to_string(),
+            service: service.name.clone(),
+        });
+    }
+    for (key, value) in service.env_vars.iter() {
+        changes.push(Change::SetEnvVar {
+            project: project.to_string(),
+            service: service.name.clone(),
+            key: key.clone(),
+            value: resolve_value(value)?,
+            exists: false,
+        });
+    }
+    Ok(())
+}
+
+fn plan_existing_project(
+    api_client: &ApiClient,
+    project: &Project,
+    project_manifest: &ProjectManifest,
+    changes: &mut Vec<Change>,
+) -> ManifestResult<()> {
+    let databases = api_client.list_resources(
+        &project.slug,
+        Some(&ResourceListFilter {
+            kind: ResourceKind::Database,
+            identifier: None,
+        }),
+    )?;
+    for database in project_manifest.databases.iter() {
+        if !databases.iter().any(|db| db.name == database.name) {
+            changes.push(Change::CreateDatabase {
+                project: project.name.clone(),
+                database: database.clone(),
+            });
+        }
+    }
+
+    let services = api_client.list_services(&project.slug, None)?;
+    let buckets = api_client.list_resources(
+        &project.slug,
+        Some(&ResourceListFilter {
+            kind: ResourceKind::Bucket,
+            identifier: None,
+        }),
+    )?;
+    for service_manifest in project_manifest.services.iter() {
+        let service = match services.iter().find(|s| s.name == service_manifest.name) {
+            Some(s) => s,
+            None => {
+                plan_new_service(&project.name, service_manifest, changes)?;
+                continue;
+            }
+        };
+
+        let diff = service_manifest.diff(service);
+        if !diff.is_empty() {
+            changes.push(Change::UpdateService {
+                project: project.name.clone(),
+                service: service_manifest.apply_to(service),
+                changes: diff,
+            });
+        }
+
+        if service_manifest.statics_bucket && !buckets.iter().any(|b| b.name == service.name) {
+            changes.push(Change::AddStaticsBucket {
+                project: project.name.clone(),
+                service: service.name.clone(),
+            });
+        }
+
+        let env_vars = api_client.list_env_vars(&service.slug)?;
+        for (key, value) in service_manifest.env_vars.iter() {
+            let value = resolve_value(value)?;
+            let existing = env_vars.iter().find(|e| &e.name == key);
+            if existing.map(|e| &e.value) == Some(&value) {
+                continue;
+            }
+            changes.push(Change::SetEnvVar {
+                project: project.name.clone(),
+                service: service.name.clone(),
+                key: key.clone(),
+                value,
+                exists: existing.is_some(),
+            });
+        }
+    }
+    Ok(())
+}
+
+pub fn plan(api_client: &ApiClient, manifest_path: &str) {
+    let manifest = match Manifest::load(manifest_path) {
+        Ok(m) => m,
+        Err(err) => {
+            eprintln!("Error: {}", err);
+            exit(1);
+        }
+    };
+    match compute_plan(api_client, &manifest) {
+        Ok(plan) => plan.print(),
+        Err(err) => {
+            eprintln!("Error: {}", err);
+            exit(1);
+        }
+    }
+}
@@ -0,0 +1,256 @@
+use std::collections::BTreeMap;
+use std::env;
+use std::error::Error;
+use std::{fmt, fs, io};
+
+use serde::{Deserialize, Serialize};
+
+use crate::api_client::{ApiClientError, CreateDbRequest, CreateServiceRequest};
+use crate::schemas::Service;
+
+#[derive(Debug)]
+pub enum ManifestError {
+    ReadError(String),
+    ParseError(String),
+    NotFound(String),
+    APIError(ApiClientError),
+    ApplyError(String),
+}
+
+pub type ManifestResult<T> = Result<T, ManifestError>;
+
+impl Error for ManifestError {}
+
+impl fmt::Display for ManifestError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ManifestError::ReadError(ref cause) => write!(f, "{}", cause),
+            ManifestError::ParseError(ref cause) => write!(f, "{}", cause),
+            ManifestError::NotFound(ref cause) => write!(f, "{}", cause),
+            ManifestError::APIError(ref err) => err.fmt(f),
+            ManifestError::ApplyError(ref cause) => write!(f, "{}", cause),
+        }
+    }
+}
+
+impl From<io::Error> for ManifestError {
+    fn from(err: io::Error) -> ManifestError {
+        ManifestError::ReadError(err.to_string())
+    }
+}
+
+impl From<toml::de::Error> for ManifestError {
+    fn from(err: toml::de::Error) -> ManifestError {
+        ManifestError::ParseError(err.to_string())
+    }
+}
+
+impl From<ApiClientError> for ManifestError {
+    fn from(err: ApiClientError) -> ManifestError {
+        ManifestError::APIError(err)
+    }
+}
+
+/// Desired state of an environment, as described in `chiliseed.toml`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Manifest {
+    pub environment: EnvironmentManifest,
+    #[serde(default)]
+    pub projects: Vec<ProjectManifest>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EnvironmentManifest {
+    pub name: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProjectManifest {
+    pub name: String,
+    #[serde(default)]
+    pub services: Vec<ServiceManifest>,
+    #[serde(default)]
+    pub databases: Vec<DatabaseManifest>,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+fn default_dockerfile() -> String {
+    "Dockerfile".to_string()
+}
+
+fn default_engine() -> String {
+    "postgres".to_string()
+}
+
+fn default_preset() -> String {
+    "dev".to_string()
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ServiceManifest {
+    pub name: String,
+    #[serde(default = "default_true")]
+    pub has_web_interface: bool,
+    #[serde(default)]
+    pub subdomain: String,
+    #[serde(default)]
+    pub container_port: u32,
+    #[serde(default)]
+    pub alb_port_http: u32,
+    #[serde(default)]
+    pub alb_port_https: u32,
+    #[serde(default)]
+    pub health_check_endpoint: String,
+    #[serde(default = "default_dockerfile")]
+    pub default_dockerfile_path: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub default_dockerfile_target: Option<String>,
+    #[serde(default)]
+    pub statics_bucket: bool,
+    #[serde(default)]
+    pub env_vars: BTreeMap<String, String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DatabaseManifest {
+    pub name: String,
+    pub username: String,
+    #[serde(default = "default_engine")]
+    pub engine: String,
+    #[serde(default = "default_preset")]
+    pub preset: String,
+}
+
+/// Placeholder for a value that must not be stored in the manifest. It is
+/// substituted from the environment variable with the same name on apply.
+pub fn placeholder(key: &str) -> String {
+    format!("${{{}}}", key)
+}
+
+/// Substitute `${NAME}` placeholder with the value of `NAME` environment
+/// variable, other values are returned as is.
+pub fn resolve_value(value: &str) -> ManifestResult<String> {
+    if !(value.starts_with("${") && value.ends_with('}')) {
+        return Ok(value.to_string());
+    }
+    let name = &value[2..value.len() - 1];
+    env::var(name).map_err(|_err| {
+        ManifestError::NotFound(format!(
+            "Environment variable {} required by the manifest is not set",
+            name
+        ))
+    })
+}
+
+impl Manifest {
+    pub fn load(path: &str) -> ManifestResult<Manifest> {
+        let content = fs::read_to_string(path)
+            .map_err(|err| ManifestError::ReadError(format!("Cannot read {}: {}", path, err)))?;
+        let manifest: Manifest = toml::from_str(&content)?;
+        Ok(manifest)
+    }
+}
+
+impl ServiceManifest {
+    pub fn to_create_request(&self) -> CreateServiceRequest {
+        CreateServiceRequest {
+            name: self.name.clone(),
+            has_web_interface: self.has_web_interface,
+            default_dockerfile_path: self.default_dockerfile_path.clone(),
+            default_dockerfile_target: self.default_dockerfile_target.clone(),
+            subdomain: self.subdomain.clone(),
+            container_port: self.container_port.to_string(),
+            alb_port_http: self.alb_port_http.to_string(),
+            alb_port_https: self.alb_port_https.to_string(),
+            health_check_endpoint: self.health_check_endpoint.clone(),
+        }
+    }
+
+    /// Existing service updated with parameters from the manifest.
+    pub fn apply_to(&self, service: &Service) -> Service {
+        Service {
+            slug: service.slug.clone(),
+            name: self.name.clone(),
+            subdomain: self.subdomain.clone(),
+            container_port: self.container_port,
+            alb_port_http: self.alb_port_http,
+            alb_port_https: self.alb_port_https,
+            health_check_endpoint: self.health_check_endpoint.clone(),
+            ecr_repo_url: service.ecr_repo_url.clone(),
+            default_dockerfile_path: self.default_dockerfile_path.clone(),
+            default_dockerfile_target: self.default_dockerfile_target.clone(),
+            has_web_interface: self.has_web_interface,
+        }
+    }
+
+    /// Human readable list of parameters that differ from existing service.
+    pub fn diff(&self, service: &Service) -> Vec<String> {
+        let mut changes = Vec::new();
+        let mut compare = |field: &str, current: String, desired: String| {
+            if current != desired {
+                changes.push(format!("{}: {} -> {}", field, current, desired));
+            }
+        };
+        compare(
+            "has_web_interface",
+            service.has_web_interface.to_string(),
+            self.has_web_interface.to_string(),
+        );
+        if self.has_web_interface {
+            compare(
+                "subdomain",
+                service.subdomain.clone(),
+                self.subdomain.clone(),
+            );
+            compare(
+                "container_port",
+                service.container_port.to_string(),
+                self.container_port.to_string(),
+            );
+            compare(
+                "alb_port_http",
+                service.alb_port_http.to_string(),
+                self.alb_port_http.to_string(),
+            );
+            compare(
+                "alb_port_https",
+                service.alb_port_https.to_string(),
+                self.alb_port_https.to_string(),
+            );
+            compare(
+                "health_check_endpoint",
+                service.health_check_endpoint.clone(),
+                self.health_check_endpoint.clone(),
+            );
+        }
+        compare(
+            "default_dockerfile_path",
+            service.default_dockerfile_path.clone(),
+            self.default_dockerfile_path.clone(),
+        );
+        compare(
+            "default_dockerfile_target",
+            service
+                .default_dockerfile_target
+                .clone()
+                .unwrap_or_default(),
+            self.default_dockerfile_target.clone().unwrap_or_default(),
+        );
+        changes
+    }
+}
+
+impl DatabaseManifest {
+    pub fn to_create_request(&self, project_slug: &str) -> CreateDbRequest {
+        CreateDbRequest {
+            name: self.name.clone(),
+            username: self.username.clone(),
+            engine: self.engine.clone(),
+            preset: self.preset.clone(),
+            project: project_slug.to_string(),
+        }
+    }
+}
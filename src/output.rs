@@ -0,0 +1,70 @@
+use std::fmt;
+use std::str::FromStr;
+
+use serde::Serialize;
+
+const TABLE: &str = "table";
+const WIDE: &str = "wide";
+const JSON: &str = "json";
+const YAML: &str = "yaml";
+
+/// Format in which list and describe commands print their results.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum OutputFormat {
+    Table,
+    Wide,
+    Json,
+    Yaml,
+}
+
+impl OutputFormat {
+    pub fn variants() -> [&'static str; 4] {
+        [TABLE, WIDE, JSON, YAML]
+    }
+
+    /// Whether output is meant for machines rather than humans.
+    pub fn is_structured(self) -> bool {
+        self == OutputFormat::Json || self == OutputFormat::Yaml
+    }
+
+    pub fn is_wide(self) -> bool {
+        self == OutputFormat::Wide
+    }
+}
+
+impl FromStr for OutputFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            TABLE => Ok(OutputFormat::Table),
+            WIDE => Ok(OutputFormat::Wide),
+            JSON => Ok(OutputFormat::Json),
+            YAML => Ok(OutputFormat::Yaml),
+            _ => Err(format!("Unsupported output format: {}", s)),
+        }
+    }
+}
+
+impl fmt::Display for OutputFormat {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            OutputFormat::Table => write!(f, "{}", TABLE),
+            OutputFormat::Wide => write!(f, "{}", WIDE),
+            OutputFormat::Json => write!(f, "{}", JSON),
+            OutputFormat::Yaml => write!(f, "{}", YAML),
+        }
+    }
+}
+
+/// Print data as json or yaml to stdout.
+pub fn print_structured<T: Serialize>(output: OutputFormat, data: &T) {
+    let serialized = match output {
+        OutputFormat::Yaml => serde_yaml::to_string(data).map_err(|err| err.to_string()),
+        _ => serde_json::to_string_pretty(data).map_err(|err| err.to_string()),
+    };
+    match serialized {
+        Ok(s) => println!("{}", s),
+        Err(err) => eprintln!("Error serializing output: {}", err),
+    }
+}
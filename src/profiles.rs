@@ -0,0 +1,110 @@
+use crate::config::{Config, Profile, DEFAULT_PROFILE};
+use crate::credentials;
+use crate::utils::{add_row_to_output_table, get_output_table};
+
+fn load_config() -> Option<Config> {
+    match Config::load() {
+        Ok(config) => Some(config),
+        Err(err) => {
+            eprintln!("Error: {}", err);
+            None
+        }
+    }
+}
+
+fn save_config(config: &Config) -> bool {
+    match config.save() {
+        Ok(()) => true,
+        Err(err) => {
+            eprintln!("Error saving config: {}", err);
+            false
+        }
+    }
+}
+
+pub fn list_profiles() {
+    let config = match load_config() {
+        Some(c) => c,
+        None => return,
+    };
+    if config.profiles.is_empty() {
+        println!("You have not created any profiles yet.");
+        return;
+    }
+
+    let current = config.current_profile_name();
+    let mut table = get_output_table();
+    add_row_to_output_table(
+        &mut table,
+        vec!["", "Name", "Host", "User", "Environment", "Project"],
+    );
+    for (name, profile) in config.profiles.iter() {
+        let marker = if *name == current { "*" } else { "" };
+        add_row_to_output_table(
+            &mut table,
+            vec![
+                marker,
+                name,
+                profile.host.as_deref().unwrap_or("-"),
+                profile.user.as_deref().unwrap_or("-"),
+                profile.environment.as_deref().unwrap_or("-"),
+                profile.project.as_deref().unwrap_or("-"),
+            ],
+        );
+    }
+    table.printstd();
+}
+
+pub fn add_profile(name: String, profile: Profile) {
+    let mut config = match load_config() {
+        Some(c) => c,
+        None => return,
+    };
+    if config.profiles.contains_key(&name) {
+        eprintln!("Profile {} already exists.", name);
+        return;
+    }
+    config.profiles.insert(name.clone(), profile);
+    if config.current_profile.is_none() && config.profiles.len() == 1 {
+        config.current_profile = Some(name.clone());
+    }
+    if save_config(&config) {
+        println!("Added profile {}", name);
+    }
+}
+
+pub fn remove_profile(name: String) {
+    let mut config = match load_config() {
+        Some(c) => c,
+        None => return,
+    };
+    if config.profiles.remove(&name).is_none() {
+        eprintln!("Profile {} does not exist.", name);
+        return;
+    }
+    if config.current_profile.as_deref() == Some(name.as_str()) {
+        config.current_profile = None;
+    }
+    if !save_config(&config) {
+        return;
+    }
+    if let Err(err) = credentials::remove(&name) {
+        eprintln!("Failed to remove credentials of profile {}: {}", name, err);
+    }
+    println!("Removed profile {}", name);
+}
+
+pub fn use_profile(name: String) {
+    let mut config = match load_config() {
+        Some(c) => c,
+        None => return,
+    };
+    if name != DEFAULT_PROFILE && !config.profiles.contains_key(&name) {
+        eprintln!("Profile {} does not exist.", name);
+        return;
+    }
+    config.current_profile = Some(name.clone());
+    if save_config(&config) {
+        println!("Switched to profile {}", name);
+    }
+}
@@ -4,6 +4,7 @@ use std::fmt;
 use text_io::read;
 
 use crate::api_client::{ApiClient, EnvListFilters, ProjectListFilters, ProjectRequest};
+use crate::output::{print_structured, OutputFormat};
 use crate::schemas::{Env, Project};
 use crate::utils::{add_row_to_output_table, await_exec_result, get_output_table};
 
@@ -59,11 +60,15 @@ where
     }
 }
 
-pub fn list_projects(api_client: &ApiClient, env_name: &str) {
+pub fn list_projects(api_client: &ApiClient, env_name: &str, output: OutputFormat) {
     get_env_and_then(api_client, env_name, |env| {
         debug!("Getting projects for environment: \n{:?}", env);
         match api_client.list_projects(&env.slug, None) {
             Ok(projects) => {
+                if output.is_structured() {
+                    print_structured(output, &projects);
+                    return;
+                }
                 if projects.is_empty() {
                     println!("Environment {} has no projects yet.", env.name);
                     return;
@@ -91,6 +96,13 @@ pub fn list_projects(api_client: &ApiClient, env_name: &str) {
                         &mut table,
                         vec!["Status", &project.last_status.status],
                     );
+                    if output.is_wide() {
+                        add_row_to_output_table(&mut table, vec!["Slug", &project.slug]);
+                        add_row_to_output_table(
+                            &mut table,
+                            vec!["Environment slug", &project.environment.slug],
+                        );
+                    }
                     table.printstd();
                 }
             }
@@ -1,14 +1,14 @@
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Status {
     pub slug: String,
     pub status: String,
     pub created_at: DateTime<Utc>,
 }
 
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Env {
     pub slug: String,
     pub name: String,
@@ -19,7 +19,7 @@ pub struct Env {
     pub last_status: Status,
 }
 
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ExecLog {
     pub slug: String,
     pub action: String,
@@ -29,7 +29,7 @@ pub struct ExecLog {
     pub ended_at: Option<DateTime<Utc>>,
 }
 
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Project {
     pub slug: String,
     pub name: String,
@@ -52,16 +52,37 @@ pub struct Service {
     pub has_web_interface: bool,
 }
 
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Deployment {
+    pub slug: String,
+    pub version: String,
+    pub deployed_by: String,
+    pub created_at: DateTime<Utc>,
+    pub is_success: Option<bool>,
+    pub log: String,
+    pub commit_sha: Option<String>,
+    pub branch: Option<String>,
+    pub commit_message: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Worker {
     pub slug: String,
     pub is_ready: bool,
+    #[serde(skip_serializing)]
     pub ssh_key: String,
     pub ssh_key_name: String,
     pub public_ip: String,
+    /// SHA256 fingerprint of worker's SSH host key. Example: SHA256:nThbg6kXUpJWGl7E1IGOCspRomTxdCARLviKw6E5SY8
+    pub host_key_fingerprint: Option<String>,
+    /// Name of the service the worker builds
+    pub service_name: Option<String>,
+    /// Version the worker was launched to build
+    pub version: Option<String>,
+    pub created_at: Option<DateTime<Utc>>,
 }
 
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ResourceConfigs {
     pub instance_type: String,
     pub engine: String,
@@ -69,12 +90,13 @@ pub struct ResourceConfigs {
     pub number_of_nodes: u32,
     pub allocated_storage: u32,
     pub username: String,
+    #[serde(skip_serializing)]
     pub password: String,
     pub address: String,
     pub port: u32,
 }
 
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Resource {
     pub slug: String,
     pub identifier: String,
@@ -88,7 +110,7 @@ pub struct Resource {
     pub updated_at: Option<DateTime<Utc>>,
 }
 
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct BucketConfigs {
     pub bucket: String,
     pub arn: String,
@@ -98,7 +120,7 @@ pub struct BucketConfigs {
     pub region: String,
 }
 
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Bucket {
     pub slug: String,
     pub identifier: String,
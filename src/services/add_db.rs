@@ -1,10 +1,9 @@
 use crate::api_client::{AddDbRequest, ApiClient};
 use crate::db::set_env_vars;
-use crate::env_vars;
-use crate::schemas::{Resource, Service};
+use crate::schemas::{Project, Resource, Service};
 use crate::utils::await_exec_result;
 
-pub fn add_database(api_client: &ApiClient, service: Service, db: Resource) {
+pub fn add_database(api_client: &ApiClient, project: &Project, service: Service, db: Resource) {
     let run_log = match api_client.add_db_to_service(
         &service.slug,
         &AddDbRequest {
@@ -24,5 +23,5 @@ pub fn add_database(api_client: &ApiClient, service: Service, db: Resource) {
         return;
     }
 
-    set_env_vars(&db);
+    set_env_vars(api_client, project, &db);
 }
@@ -1,12 +1,23 @@
+use std::process::exit;
+
 use text_io::read;
 
+use super::types::ServiceResult;
 use crate::api_client::{ApiClient, CreateServiceRequest};
+use crate::commands::ServiceParams;
 use crate::environments::get_env;
 use crate::projects::get_project;
-use crate::services::{ask_for_value, ask_yes_no};
+use crate::services::{ask_for_value, ask_yes_no, parse_port, value_or_ask};
 use crate::utils::await_exec_result;
 
-pub fn create_service(api_client: &ApiClient, env_name: &str, project_name: &str) {
+const DEFAULT_DOCKERFILE: &str = "Dockerfile";
+
+pub fn create_service(
+    api_client: &ApiClient,
+    env_name: &str,
+    project_name: &str,
+    params: ServiceParams,
+) {
     let env = match get_env(api_client, env_name) {
         Ok(e) => e,
         Err(err) => {
@@ -22,13 +33,42 @@ pub fn create_service(api_client: &ApiClient, env_name: &str, project_name: &str
         }
     };
 
-    println!("Your service name (example: api): ");
-    let name: String = read!();
+    let service = match get_create_service_request(params) {
+        Ok(s) => s,
+        Err(err) => {
+            eprintln!("Error: {}", err);
+            exit(1);
+        }
+    };
+
+    let run_slug = match api_client.create_service(&service, &project.slug) {
+        Ok(resp) => resp.log,
+        Err(_) => return,
+    };
+
+    println!("Launching service infra: {}", service.name);
+    await_exec_result(api_client, &run_slug, None);
+}
+
+fn get_create_service_request(params: ServiceParams) -> ServiceResult<CreateServiceRequest> {
+    let non_interactive = params.non_interactive;
+    let has_web_params = params.has_web_params();
 
-    let has_web_interface = ask_yes_no(
-        "Does your service need web interface? [y/n]: ".to_string(),
-        true,
-    );
+    let name = value_or_ask(params.name, "--name", non_interactive, || {
+        println!("Your service name (example: api): ");
+        read!()
+    })?;
+
+    let has_web_interface = if params.no_web {
+        false
+    } else if has_web_params || non_interactive {
+        true
+    } else {
+        ask_yes_no(
+            "Does your service need web interface? [y/n]: ".to_string(),
+            true,
+        )
+    };
 
     let mut subdomain = "".to_string();
     let mut container_port = "".to_string();
@@ -37,54 +77,87 @@ pub fn create_service(api_client: &ApiClient, env_name: &str, project_name: &str
     let mut health_check_endpoint = "".to_string();
 
     if has_web_interface {
-        println!("Your service subdomain (for example, for api.example.com, subdomain is `api`): ");
-        subdomain = read!();
+        subdomain = value_or_ask(params.subdomain, "--subdomain", non_interactive, || {
+            println!(
+                "Your service subdomain (for example, for api.example.com, subdomain is `api`): "
+            );
+            read!()
+        })?;
 
-        println!("On what port will your container listen (example: 8000): ");
-        container_port = read!();
+        let port = value_or_ask(
+            params.container_port.map(|p| p.to_string()),
+            "--container-port",
+            non_interactive,
+            || {
+                println!("On what port will your container listen (example: 8000): ");
+                read!()
+            },
+        )?;
+        container_port = parse_port(&port, "--container-port")?.to_string();
 
-        println!("On what port do you want the load balancer to listen for HTTP traffic for this service (example: 80): ");
-        alb_port_http = read!();
+        let port = value_or_ask(
+            params.alb_http_port.map(|p| p.to_string()),
+            "--alb-http-port",
+            non_interactive,
+            || {
+                println!("On what port do you want the load balancer to listen for HTTP traffic for this service (example: 80): ");
+                read!()
+            },
+        )?;
+        alb_port_http = parse_port(&port, "--alb-http-port")?.to_string();
 
-        println!("On what port do you want the load balancer to listen for HTTPS traffic for this service (example: 443): ");
-        alb_port_https = read!();
+        let port = value_or_ask(
+            params.alb_https_port.map(|p| p.to_string()),
+            "--alb-https-port",
+            non_interactive,
+            || {
+                println!("On what port do you want the load balancer to listen for HTTPS traffic for this service (example: 443): ");
+                read!()
+            },
+        )?;
+        alb_port_https = parse_port(&port, "--alb-https-port")?.to_string();
 
-        println!("What is your health check endpoint (example: /api/health/check/): ");
-        health_check_endpoint = read!();
+        health_check_endpoint = value_or_ask(
+            params.health_check,
+            "--health-check",
+            non_interactive,
+            || {
+                println!("What is your health check endpoint (example: /api/health/check/): ");
+                read!()
+            },
+        )?;
     }
 
-    let mut default_dockerfile_path = "Dockerfile".to_string();
-    default_dockerfile_path = ask_for_value(
-        format!(
-            "Path to service's dockerfile, relative to project root [defaults to '{}']: ",
-            default_dockerfile_path
+    let default_dockerfile_path = match params.dockerfile {
+        Some(path) => path,
+        None if non_interactive => DEFAULT_DOCKERFILE.to_string(),
+        None => ask_for_value(
+            format!(
+                "Path to service's dockerfile, relative to project root [defaults to '{}']: ",
+                DEFAULT_DOCKERFILE
+            ),
+            DEFAULT_DOCKERFILE.to_string(),
         ),
-        default_dockerfile_path,
-    );
+    };
 
-    let mut default_dockerfile_target = "".to_string();
-    default_dockerfile_target = ask_for_value(
-        "Optional specific dockerfile target to build: ".to_string(),
-        default_dockerfile_target,
-    );
+    let default_dockerfile_target = match params.target {
+        Some(target) => Some(target),
+        None if non_interactive => None,
+        None => Some(ask_for_value(
+            "Optional specific dockerfile target to build: ".to_string(),
+            "".to_string(),
+        )),
+    };
 
-    let service = CreateServiceRequest {
+    Ok(CreateServiceRequest {
         name,
         has_web_interface,
         default_dockerfile_path,
-        default_dockerfile_target: Some(default_dockerfile_target),
+        default_dockerfile_target: default_dockerfile_target.filter(|t| !t.is_empty()),
         subdomain,
         container_port,
         alb_port_http,
         alb_port_https,
         health_check_endpoint,
-    };
-
-    let run_slug = match api_client.create_service(&service, &project.slug) {
-        Ok(resp) => resp.log,
-        Err(_) => return,
-    };
-
-    println!("Launching service infra: {}", service.name);
-    await_exec_result(api_client, &run_slug, None);
+    })
 }
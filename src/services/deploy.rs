@@ -1,177 +1,333 @@
-use std::fs;
-use std::fs::File;
-use std::io::{BufRead, BufReader, Read, Write};
-use std::net::TcpStream;
+use std::io::{BufRead, BufReader};
 use std::path::Path;
-
-use flate2::write::GzEncoder;
-use flate2::Compression;
-use globset::{Glob, GlobSetBuilder};
-use ssh2::Session;
-use uuid::Uuid;
-use walkdir::WalkDir;
-
+use std::process::exit;
+use std::thread;
+
+use chrono::Local;
+use ssh2::{ExtendedData, Session};
+
+use super::git::Revision;
+use super::health::wait_healthy;
+use super::local_build::build_locally;
+use super::package::package;
+use super::progress::UploadBars;
+use super::ssh::{get_session, SshOptions};
 use super::types::ServiceResult;
-use crate::api_client::{ApiClient, LaunchWorkerRequest, ServiceDeployRequest};
-use crate::schemas::Service;
+use super::upload::{get_build_manifest, upload_build_context, BuildFile};
+use super::version::{
+    get_build_version, resolve_strategy, validate_version, BuildVersion, VersionStrategy,
+};
+use crate::api_client::{
+    ApiClient, ImageListFilters, LaunchWorkerRequest, ServiceDeployRequest, ServiceImage,
+};
+use crate::commands::DeployParams;
+use crate::schemas::{Env, Service};
 use crate::services::types::ServiceError;
-use crate::utils::{await_exec_result, exec_command_with_output};
+use crate::utils::{
+    add_row_to_output_table, await_exec_result, get_output_table, output_prefix, set_output_prefix,
+};
 
-const BUILD_WORKER_USER: &str = "ubuntu";
-const BUILD_LOCATION: &str = "_build";
+/// User build workers are accessed with.
+pub const BUILD_WORKER_USER: &str = "ubuntu";
 
 /// This command must be run from the same location as the dockerfile of the service to be deployed.
-/// First, builds an image and pushes it to ECR.
+/// First, builds an image and pushes it to ECR, on a build worker or locally
+/// with `--local-build`.
 /// Second, triggers deploy of the service on the server.
-pub fn deploy(api_client: &ApiClient, service: Service, build_args: Option<Vec<String>>) {
-    debug!("got service: {:?}", service);
-    let ecr_repo_uri = service.ecr_repo_url.unwrap();
-
-    debug!("Building image and pushing to ECR: {}", ecr_repo_uri);
-
-    let (success, version) =
-        exec_command_with_output("git", vec!["rev-parse", "--short", "HEAD"]).unwrap();
+/// Multiple services are deployed in parallel, exits with non zero code if
+/// any of them failed.
+pub fn deploy(api_client: &ApiClient, env: &Env, services: Vec<Service>, params: DeployParams) {
+    debug!("got services: {:?}", services);
+    if params.dry_run {
+        package(&services, true, None);
+        return;
+    }
 
-    let version_sha = match success {
-        true => sanitize_word(version),
-        false => {
-            eprintln!("Error getting git sha");
-            return;
-        }
+    let strategy = match params.version {
+        Some(_) => None,
+        None => match resolve_strategy(params.version_from) {
+            Ok(s) => Some(s),
+            Err(err) => {
+                eprintln!("Error: {}", err);
+                exit(1);
+            }
+        },
     };
-    println!("Version: {}", version_sha);
 
-    debug!("version to be deployed: {}", version_sha);
-    let (worker_slug, run_slug) = match api_client.launch_worker(
-        &LaunchWorkerRequest {
-            version: version_sha.trim().to_string(),
-        },
-        &service.slug,
-    ) {
-        Ok(resp) => (resp.build, resp.log),
-        Err(err) => {
-            eprintln!("Error: {}", err);
-            return;
+    // files to upload are the same for all services, find them only once
+    let manifest = if strategy.is_some()
+        && (!params.local_build || strategy == Some(VersionStrategy::ContentHash))
+    {
+        match get_build_manifest(Path::new(".")) {
+            Ok(m) => Some(m),
+            Err(err) => {
+                eprintln!("Error: {}", err);
+                exit(1);
+            }
         }
+    } else {
+        None
     };
 
-    if let Some(exec_log_slug) = run_slug {
-        println!("Launching build worker: {}", service.name);
-        if !await_exec_result(api_client, &exec_log_slug, None) {
-            eprintln!("There was an error launching worker.");
-            return;
+    let version_result = match strategy {
+        Some(strategy) => get_build_version(
+            strategy,
+            params.allow_dirty,
+            manifest.as_deref().unwrap_or_default(),
+            params.build_arg.as_deref(),
+        ),
+        None => {
+            let version = params.version.clone().unwrap_or_default();
+            validate_version(&version).map(|_| BuildVersion {
+                version,
+                revision: None,
+                strategy: None,
+            })
         }
-    }
-
-    let worker = match api_client.get_worker_details(&worker_slug) {
-        Ok(w) => w,
+    };
+    let build = match version_result {
+        Ok(v) => v,
         Err(err) => {
             eprintln!("Error: {}", err);
-            return;
+            exit(1);
         }
     };
+    println!("Version: {}", build.version);
+    debug!("version to be deployed: {}", build.version);
+
+    // output of every service is prefixed with its name and upload progress
+    // bars are drawn together, services are deployed at the same time
+    let bars = UploadBars::new();
+    let results: Vec<ServiceResult<()>> = thread::scope(|scope| {
+        scope.spawn(|| bars.draw());
+        let handles: Vec<_> = services
+            .iter()
+            .map(|service| {
+                let (build, params, manifest, bars) = (&build, &params, manifest.as_deref(), &bars);
+                scope.spawn(move || {
+                    set_output_prefix(&service.name);
+                    deploy_service(api_client, env, service, build, params, manifest, bars)
+                })
+            })
+            .collect();
+        let results = handles
+            .into_iter()
+            .map(|handle| {
+                handle.join().unwrap_or_else(|_| {
+                    Err(ServiceError::DeploymentError(
+                        "Deployment crashed".to_string(),
+                    ))
+                })
+            })
+            .collect();
+        bars.finish();
+        results
+    });
 
-    // create pem key file with read only permission
-    let ssh_key_path = format!("{}.pem", &worker.ssh_key_name);
-    if !Path::new(&ssh_key_path).exists() {
-        let mut ssh_key_file = File::create(ssh_key_path.clone()).unwrap();
-        ssh_key_file.write_all(worker.ssh_key.as_bytes()).unwrap();
-        let mut permissions = ssh_key_file.metadata().unwrap().permissions();
-        permissions.set_readonly(true);
-        ssh_key_file.set_permissions(permissions).unwrap();
+    let mut failed = false;
+    let mut table = get_output_table();
+    for (service, result) in services.iter().zip(results.iter()) {
+        let status = match result {
+            Ok(()) => "deployed".to_string(),
+            Err(err) => {
+                failed = true;
+                format!("failed: {}", err)
+            }
+        };
+        add_row_to_output_table(&mut table, vec![&service.name, &status]);
     }
-
-    // prepare build directory
-    match setup_deployment_dir() {
-        Ok(()) => debug!("deployment dir is ready"),
-        Err(err) => {
-            eprintln!("Error: {}", err);
-            return;
-        }
+    println!();
+    table.printstd();
+    if failed {
+        exit(1);
     }
-    // create tar.gz build directory
-    let build_tarball = match create_build_tarball() {
-        Ok(tarball) => {
-            println!("Build tarballed ok");
-            tarball
-        }
-        Err(err) => {
-            eprintln!("Error: {}", err);
-            return;
-        }
-    };
+}
 
-    let ssh_conn = match get_session(&worker.public_ip, "ubuntu", &ssh_key_path) {
-        Ok(ss) => {
-            debug!("connected to build worker");
-            ss
-        }
-        Err(err) => {
-            eprintln!("Error: {}", err);
-            return;
-        }
-    };
-    // upload tar.gz to worker server
-    match upload_build_tarball_to_worker(&ssh_conn, &build_tarball) {
-        Ok(()) => println!("Build uploaded to worker"),
-        Err(err) => {
-            eprintln!("Error: {}", err);
-            return;
-        }
-    };
+/// Build image of the service, unless deploying an existing version, and
+/// deploy it. With `--wait-healthy` also waits for health check to pass.
+fn deploy_service(
+    api_client: &ApiClient,
+    env: &Env,
+    service: &Service,
+    build: &BuildVersion,
+    params: &DeployParams,
+    manifest: Option<&[BuildFile]>,
+    bars: &UploadBars,
+) -> ServiceResult<()> {
+    let version = build.version.as_str();
+    let ecr_repo_uri = service.ecr_repo_url.as_ref().ok_or_else(|| {
+        ServiceError::DeploymentError(format!(
+            "Service {} has no image repository yet.",
+            service.name
+        ))
+    })?;
+
+    if params.version.is_some() {
+        check_image_exists(api_client, service, version)?;
+    } else if build.is_content_addressed() && find_image(api_client, service, version)?.is_some() {
+        println!(
+            "{}Image {} of service {} is already built, skipping build",
+            output_prefix(),
+            version,
+            service.name
+        );
+    } else if params.local_build {
+        build_locally(
+            api_client,
+            service,
+            ecr_repo_uri,
+            version,
+            params.build_arg.clone(),
+        )?;
+    } else {
+        debug!("Building image and pushing to ECR: {}", ecr_repo_uri);
+        let ssh_options = SshOptions {
+            use_agent: params.ssh_agent,
+            known_hosts: params.known_hosts.clone(),
+        };
+        build_on_worker(
+            api_client,
+            service,
+            version,
+            params.build_arg.clone(),
+            &ssh_options,
+            manifest.unwrap_or_default(),
+            bars,
+        )?;
+    }
 
-    // unpack the tarball on server
-    match unpack_and_build_tarball(&ssh_conn, &build_tarball, build_args, &version_sha) {
-        Ok(()) => println!("Build extracted"),
-        Err(err) => {
-            eprintln!("Error: {}", err);
-            return;
-        }
+    if !deploy_version(api_client, service, version, build.revision.as_ref()) {
+        return Err(ServiceError::DeploymentError(
+            "Deployment did not finish successfully".to_string(),
+        ));
     }
+    if params.wait_healthy {
+        wait_healthy(env, service, version, params.health_timeout)?;
+    }
+    Ok(())
+}
 
-    // trigger deploy service
-    let run_slug = match api_client.deploy_service(
+/// Trigger deploy of the service with image of the version and wait for it.
+/// Revision the image was built from is recorded with the deployment.
+pub fn deploy_version(
+    api_client: &ApiClient,
+    service: &Service,
+    version: &str,
+    revision: Option<&Revision>,
+) -> bool {
+    let resp = match api_client.deploy_service(
         &service.slug,
         &ServiceDeployRequest {
-            version: version_sha.trim().to_string(),
+            version: version.trim().to_string(),
+            commit_sha: revision.map(|r| r.sha.clone()),
+            branch: revision.and_then(|r| r.branch.clone()),
+            commit_message: revision.map(|r| r.message.clone()),
         },
     ) {
-        Ok(resp) => resp.log,
+        Ok(resp) => resp,
         Err(err) => {
             eprintln!("Error: {}", err);
-            return;
+            return false;
         }
     };
 
-    println!("Deploying service: {}", service.name);
-    await_exec_result(api_client, &run_slug, None);
+    println!("{}Deploying service: {}", output_prefix(), service.name);
+    debug!("deployment: {}", resp.deployment);
+    await_exec_result(api_client, &resp.log, None)
 }
 
-fn unpack_and_build_tarball(
-    ssh_conn: &Session,
-    build_tarball: &str,
-    build_args: Option<Vec<String>>,
+/// Image of the version in the service's repository.
+fn find_image(
+    api_client: &ApiClient,
+    service: &Service,
     version: &str,
+) -> ServiceResult<Option<ServiceImage>> {
+    let images = api_client.list_images(
+        &service.slug,
+        &ImageListFilters {
+            tag: Some(version.to_string()),
+        },
+    )?;
+    Ok(images.into_iter().find(|image| image.tag == version))
+}
+
+/// Deploying already built version requires its image in the service's
+/// repository.
+pub fn check_image_exists(
+    api_client: &ApiClient,
+    service: &Service,
+    version: &str,
+) -> ServiceResult<()> {
+    match find_image(api_client, service, version)? {
+        Some(image) => {
+            println!(
+                "{}Found image {} of service {} pushed at {}",
+                output_prefix(),
+                version,
+                service.name,
+                image.pushed_at.to_rfc2822()
+            );
+            Ok(())
+        }
+        None => Err(ServiceError::DeploymentError(format!(
+            "Image with tag {} was not found in repository of service {}",
+            version, service.name
+        ))),
+    }
+}
+
+/// Build the image on a remote build worker, which pushes it to ECR.
+fn build_on_worker(
+    api_client: &ApiClient,
+    service: &Service,
+    version_sha: &str,
+    build_args: Option<Vec<String>>,
+    ssh_options: &SshOptions,
+    manifest: &[BuildFile],
+    bars: &UploadBars,
 ) -> ServiceResult<()> {
-    println!("Extracting build package");
-    exec_cmd_on_server(
-        ssh_conn,
-        &format!("mkdir -p /home/{}/deployment", BUILD_WORKER_USER),
+    let resp = api_client.launch_worker(
+        &LaunchWorkerRequest {
+            version: version_sha.trim().to_string(),
+        },
+        &service.slug,
     )?;
+    let worker_slug = resp.build;
+
+    if let Some(exec_log_slug) = resp.log {
+        println!(
+            "{}Launching build worker: {}",
+            output_prefix(),
+            service.name
+        );
+        if !await_exec_result(api_client, &exec_log_slug, None) {
+            return Err(ServiceError::DeploymentError(
+                "There was an error launching worker.".to_string(),
+            ));
+        }
+    }
 
-    exec_cmd_on_server(
-        ssh_conn,
-        &format!(
-            "tar -xzvf /tmp/{} -C /home/{}/deployment",
-            build_tarball, BUILD_WORKER_USER
-        ),
+    let worker = api_client.get_worker_details(&worker_slug)?;
+
+    let ssh_conn = get_session(&worker, BUILD_WORKER_USER, ssh_options)?;
+    debug!("connected to build worker");
+
+    upload_build_context(
+        &ssh_conn,
+        &format!("/home/{}/deployment", BUILD_WORKER_USER),
+        manifest,
+        bars,
     )?;
+    println!("{}Build uploaded to worker", output_prefix());
 
-    debug!("Removing build tarball");
-    fs::remove_file(build_tarball)?;
-    debug!("Removing build directory");
-    fs::remove_dir_all(BUILD_LOCATION)?;
+    run_build(&ssh_conn, build_args, version_sha)
+}
 
+fn run_build(
+    ssh_conn: &Session,
+    build_args: Option<Vec<String>>,
+    version: &str,
+) -> ServiceResult<()> {
     let mut build_cmd = format!(
         "/home/{}/chiliseed-build-worker -v {}",
         BUILD_WORKER_USER, version
@@ -191,144 +347,27 @@ fn unpack_and_build_tarball(
     Ok(())
 }
 
-fn create_build_tarball() -> ServiceResult<String> {
-    let uuid = Uuid::new_v4();
-    let build_tar_name = format!("build_{}.tar.gz", uuid.to_simple());
-    let build_tar = File::create(build_tar_name.clone())?;
-    let encoder = GzEncoder::new(build_tar, Compression::default());
-    let mut tar = tar::Builder::new(encoder);
-    tar.append_dir_all("build", BUILD_LOCATION)?;
-    Ok(build_tar_name)
-}
-
-fn upload_build_tarball_to_worker(ssh_conn: &Session, build_tarball: &str) -> ServiceResult<()> {
-    println!("Uploading {} to build worker", build_tarball);
-    let mut deployment_package_fp = File::open(build_tarball.clone())?;
-    let pck_meta = deployment_package_fp.metadata()?;
-    let mut channel = ssh_conn.scp_send(
-        Path::new(&format!("/tmp/{}", build_tarball)),
-        0o644,
-        pck_meta.len(),
-        None,
-    )?;
-
-    loop {
-        let mut buffer = Vec::new();
-        let read_bytes = std::io::Read::by_ref(&mut deployment_package_fp)
-            .take(1000)
-            .read_to_end(&mut buffer)?;
-        if read_bytes == 0 {
-            break;
-        }
-        channel.write(&buffer)?;
-    }
-
-    Ok(())
-}
-
-fn get_session(server_ip: &str, server_user: &str, ssh_key: &str) -> ServiceResult<Session> {
-    let tcp = TcpStream::connect(format!("{}:22", server_ip))?;
-    let mut sess = Session::new()?;
-    sess.set_tcp_stream(tcp);
-    sess.handshake()?;
-    sess.userauth_pubkey_file(server_user, None, &Path::new(&ssh_key), None)?;
-    Ok(sess)
-}
-
+/// Run command on the worker, printing its output prefixed with time.
 fn exec_cmd_on_server(ssh_conn: &Session, cmd: &str) -> ServiceResult<i32> {
-    println!("[remote]: {}", cmd);
+    let prefix = output_prefix();
+    println!("{}[remote]: {}", prefix, cmd);
     let mut channel = ssh_conn.channel_session()?;
-    channel.exec(cmd).unwrap();
-    loop {
-        let mut buffer = Vec::new();
-        let n = std::io::Read::by_ref(&mut channel)
-            .take(10)
-            .read_to_end(&mut buffer)
-            .unwrap();
-        if n == 0 {
-            let mut s = String::new();
-            channel.stderr().read_to_string(&mut s).unwrap();
-            eprintln!("{}", s);
-            break;
-        }
-        print!("{}", String::from_utf8_lossy(&buffer));
+    channel.handle_extended_data(ExtendedData::Merge)?;
+    channel.exec(cmd)?;
+
+    let mut reader = BufReader::new(&mut channel);
+    let mut line = Vec::new();
+    while reader.read_until(b'\n', &mut line)? > 0 {
+        println!(
+            "{}[worker {}] {}",
+            prefix,
+            Local::now().format("%H:%M:%S"),
+            String::from_utf8_lossy(&line).trim_end()
+        );
+        line.clear();
     }
-    channel.wait_close().unwrap();
-    Ok(channel.exit_status().unwrap())
-}
+    drop(reader);
 
-fn setup_deployment_dir() -> ServiceResult<()> {
-    if Path::new(BUILD_LOCATION).exists() {
-        fs::remove_dir_all(BUILD_LOCATION)?;
-    }
-
-    fs::create_dir(BUILD_LOCATION)?;
-
-    let gitignore = File::open(".gitignore")?;
-    let mut ignores: Vec<String> = BufReader::new(gitignore)
-        .lines()
-        .filter_map(|line| line.ok())
-        .filter(|line| !line.trim().is_empty())
-        .collect();
-
-    ignores.push("*.pem".to_string());
-    ignores.push(".git/*".to_string());
-    ignores.push("_build/*".to_string());
-    ignores.push("*.tar.gz".to_string());
-
-    let mut path_checker = GlobSetBuilder::new();
-    ignores.iter().for_each(|ignore_pattern| {
-        let mut clean_ignore = ignore_pattern.trim().to_string();
-        if clean_ignore.starts_with("/") {
-            debug!("Adding .{} to ignore", clean_ignore);
-            clean_ignore = ".".to_string() + &clean_ignore;
-        } else if !clean_ignore.starts_with("./") {
-            debug!("Adding ./{} to ignore", clean_ignore);
-            clean_ignore = "./".to_string() + &clean_ignore;
-        }
-        if Path::new(&clean_ignore).is_dir() {
-            debug!("Adding * to {} ignore", clean_ignore);
-            clean_ignore = clean_ignore + "/*";
-        }
-        debug!("Ignoring path: {}", clean_ignore);
-        path_checker.add(Glob::new(&clean_ignore).unwrap());
-    });
-
-    let set_path_checker = path_checker.build()?;
-
-    for entry in WalkDir::new(".")
-        .follow_links(true)
-        .into_iter()
-        .filter_map(|e| e.ok())
-    {
-        let path = entry.path();
-        if path.is_dir() {
-            continue;
-        }
-
-        let matched_patterns_idx = set_path_checker.matches(path);
-        if !matched_patterns_idx.is_empty() {
-            continue;
-        }
-
-        let move_to = format!("{}/{}", &BUILD_LOCATION, path.to_str().unwrap());
-        let build_path = Path::new(&move_to);
-
-        fs::create_dir_all(build_path.parent().unwrap())?;
-        fs::copy(path, build_path)?;
-    }
-    Ok(())
-}
-
-/// Remove whitespaces and trailing new line or carriage signs
-fn sanitize_word(word: Vec<u8>) -> String {
-    let word_utf = String::from_utf8(word).unwrap();
-    let mut word_utf = String::from(word_utf.trim());
-    if word_utf.ends_with("\n") {
-        word_utf.pop();
-        if word_utf.ends_with("\r") {
-            word_utf.pop();
-        }
-    }
-    word_utf
+    channel.wait_close()?;
+    Ok(channel.exit_status()?)
 }
@@ -0,0 +1,114 @@
+use std::process::exit;
+
+use super::deploy::{check_image_exists, deploy_version};
+use super::types::{ServiceError, ServiceResult};
+use crate::api_client::ApiClient;
+use crate::output::{print_structured, OutputFormat};
+use crate::schemas::{Deployment, Service};
+use crate::utils::{add_row_to_output_table, get_output_table};
+
+fn get_status(deployment: &Deployment) -> &'static str {
+    match deployment.is_success {
+        Some(true) => "success",
+        Some(false) => "failed",
+        None => "running",
+    }
+}
+
+/// Deployments of the service, latest first.
+fn get_deployments(api_client: &ApiClient, service: &Service) -> ServiceResult<Vec<Deployment>> {
+    let mut deployments = api_client.list_deployments(&service.slug)?;
+    deployments.sort_by_key(|d| std::cmp::Reverse(d.created_at));
+    Ok(deployments)
+}
+
+pub fn list_deployments(api_client: &ApiClient, service: Service, output: OutputFormat) {
+    let deployments = match get_deployments(api_client, &service) {
+        Ok(d) => d,
+        Err(err) => {
+            eprintln!("Error getting deployments: {}", err);
+            return;
+        }
+    };
+    if output.is_structured() {
+        print_structured(output, &deployments);
+        return;
+    }
+    if deployments.is_empty() {
+        println!("Service {} was not deployed yet.", service.name);
+        return;
+    }
+
+    let mut table = get_output_table();
+    let mut header = vec![
+        "Version",
+        "Deployed by",
+        "Deployed at",
+        "Status",
+        "Execution",
+    ];
+    if output.is_wide() {
+        header.extend(vec!["Commit", "Branch", "Slug"]);
+    }
+    add_row_to_output_table(&mut table, header);
+    for deployment in deployments.iter() {
+        let created_at = deployment.created_at.to_rfc2822();
+        let mut row = vec![
+            deployment.version.as_str(),
+            deployment.deployed_by.as_str(),
+            created_at.as_str(),
+            get_status(deployment),
+            deployment.log.as_str(),
+        ];
+        if output.is_wide() {
+            row.push(deployment.commit_sha.as_deref().unwrap_or(""));
+            row.push(deployment.branch.as_deref().unwrap_or(""));
+            row.push(deployment.slug.as_str());
+        }
+        add_row_to_output_table(&mut table, row);
+    }
+    table.printstd();
+}
+
+/// Version to roll back to, the latest successful version other than the
+/// currently deployed one.
+fn get_previous_version(deployments: &[Deployment]) -> ServiceResult<String> {
+    let mut successful = deployments
+        .iter()
+        .filter(|d| d.is_success == Some(true))
+        .map(|d| d.version.as_str());
+    let current = successful.next().ok_or_else(|| {
+        ServiceError::DeploymentError("Service has no successful deployments.".to_string())
+    })?;
+    successful
+        .find(|version| *version != current)
+        .map(|version| version.to_string())
+        .ok_or_else(|| {
+            ServiceError::DeploymentError(format!(
+                "There is no successful deployment before version {}.",
+                current
+            ))
+        })
+}
+
+pub fn rollback(api_client: &ApiClient, service: Service, to_version: Option<String>) {
+    let version_result = match to_version {
+        Some(version) => check_image_exists(api_client, &service, &version).map(|_| version),
+        None => get_deployments(api_client, &service).and_then(|d| get_previous_version(&d)),
+    };
+    let version = match version_result {
+        Ok(v) => v,
+        Err(err) => {
+            eprintln!("Error: {}", err);
+            exit(1);
+        }
+    };
+
+    println!(
+        "Rolling back service {} to version {}",
+        service.name, version
+    );
+    if !deploy_version(api_client, &service, &version, None) {
+        exit(1);
+    }
+}
@@ -0,0 +1,156 @@
+use std::fs;
+use std::process::Command;
+
+use sha2::{Digest, Sha256};
+
+use super::types::{ServiceError, ServiceResult};
+
+/// Length of the hash of uncommitted changes in dirty versions.
+const DIRTY_HASH_LEN: usize = 8;
+
+/// Git revision of the working directory being deployed.
+#[derive(Debug)]
+pub struct Revision {
+    pub sha: String,
+    pub short_sha: String,
+    /// `None` with detached HEAD
+    pub branch: Option<String>,
+    pub message: String,
+    /// Hash of uncommitted changes, `None` for clean working tree
+    pub dirty_hash: Option<String>,
+}
+
+impl Revision {
+    /// Version of the revision with `-dirty-<hash>` suffix for uncommitted
+    /// changes, so different changes on top of the same commit get different
+    /// versions.
+    pub fn with_dirty_suffix(&self, version: &str) -> String {
+        match self.dirty_hash {
+            Some(ref hash) => format!("{}-dirty-{}", version, hash),
+            None => version.to_string(),
+        }
+    }
+}
+
+/// Output of the git command, missing git is an error rather than a panic,
+/// deploy works without git too.
+fn git(args: Vec<&str>) -> ServiceResult<String> {
+    debug!("git {:?}", args);
+    let output = Command::new("git")
+        .args(&args)
+        .output()
+        .map_err(|err| ServiceError::DeploymentError(format!("Error running git: {}", err)))?;
+    if !output.status.success() {
+        return Err(ServiceError::DeploymentError(format!(
+            "Error running git {}",
+            args.join(" ")
+        )));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+/// Whether the current directory is inside of a git working tree.
+pub fn is_git_checkout() -> bool {
+    git(vec!["rev-parse", "--is-inside-work-tree"]).is_ok_and(|inside| inside == "true")
+}
+
+/// Closest tag of the current commit with commits since it, or the short sha
+/// without any tags.
+pub fn get_description() -> ServiceResult<String> {
+    git(vec!["describe", "--tags", "--always"])
+}
+
+/// Revision of the current working directory.
+pub fn get_revision() -> ServiceResult<Revision> {
+    let sha = git(vec!["rev-parse", "HEAD"])?;
+    let short_sha = git(vec!["rev-parse", "--short", "HEAD"])?;
+    let branch = git(vec!["symbolic-ref", "--short", "-q", "HEAD"]).ok();
+    let message = git(vec!["log", "-1", "--format=%B"])?;
+    let dirty_hash = get_dirty_hash()?;
+    Ok(Revision {
+        sha,
+        short_sha,
+        branch,
+        message,
+        dirty_hash,
+    })
+}
+
+/// Hash of changes to tracked files and of untracked, not ignored, files.
+/// Only the current directory is packaged, changes elsewhere in the
+/// repository do not count.
+fn get_dirty_hash() -> ServiceResult<Option<String>> {
+    let diff = git(vec!["diff", "HEAD", "--binary", "--", "."])?;
+    let untracked = git(vec!["ls-files", "-z", "--others", "--exclude-standard"])?;
+    if diff.is_empty() && untracked.is_empty() {
+        return Ok(None);
+    }
+
+    let mut hasher = Sha256::new();
+    hasher.update(diff.as_bytes());
+    for path in untracked.split('\0').filter(|p| !p.is_empty()) {
+        hasher.update(path.as_bytes());
+        // dangling symlinks and such have no content to hash
+        if fs::metadata(path).is_ok_and(|m| m.is_file()) {
+            hasher.update(fs::read(path)?);
+        }
+    }
+    let hash = format!("{:x}", hasher.finalize());
+    Ok(Some(hash[..DIRTY_HASH_LEN].to_string()))
+}
+
+/// Uncommitted changes in the current directory, as listed by
+/// `git status --short`.
+pub fn get_uncommitted_changes() -> ServiceResult<String> {
+    git(vec!["status", "--short", "--", "."])
+}
+
+/// Warn when the commit is not on the remote, others would not be able to
+/// find the deployed code.
+pub fn warn_unpushed(revision: &Revision) {
+    if revision.branch.is_none() {
+        eprintln!("Warning: deploying detached HEAD {}", revision.short_sha);
+        return;
+    }
+    match git(vec!["rev-list", "--count", "@{upstream}..HEAD"]) {
+        Ok(ref count) if count != "0" => eprintln!(
+            "Warning: {} commit(s) of branch {} are not pushed",
+            count,
+            revision.branch.as_deref().unwrap_or_default()
+        ),
+        Ok(_) => {}
+        Err(_) => eprintln!(
+            "Warning: branch {} has no upstream, commit {} may not be pushed",
+            revision.branch.as_deref().unwrap_or_default(),
+            revision.short_sha
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn revision(dirty_hash: Option<&str>) -> Revision {
+        Revision {
+            sha: "1a2b3c4d5e6f".to_string(),
+            short_sha: "1a2b3c4".to_string(),
+            branch: Some("master".to_string()),
+            message: "Commit".to_string(),
+            dirty_hash: dirty_hash.map(|h| h.to_string()),
+        }
+    }
+
+    #[test]
+    fn clean_revision_keeps_version() {
+        assert_eq!(revision(None).with_dirty_suffix("1a2b3c4"), "1a2b3c4");
+    }
+
+    #[test]
+    fn dirty_revision_gets_suffix() {
+        assert_eq!(
+            revision(Some("9f8e7d6c")).with_dirty_suffix("1a2b3c4"),
+            "1a2b3c4-dirty-9f8e7d6c"
+        );
+    }
+}
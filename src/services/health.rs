@@ -0,0 +1,102 @@
+use std::thread::sleep;
+use std::time::{Duration, Instant};
+
+use reqwest::blocking::Client;
+
+use super::types::{ServiceError, ServiceResult};
+use crate::schemas::{Env, Service};
+use crate::utils::output_prefix;
+
+const HEALTH_CHECK_INTERVAL_SECS: u64 = 5;
+const REQUEST_TIMEOUT_SECS: u64 = 10;
+const VERSION_HEADER: &str = "x-version";
+const VERSION_FIELD: &str = "version";
+
+pub fn get_health_check_url(env: &Env, service: &Service) -> String {
+    format!(
+        "https://{}.{}{}",
+        service.subdomain, env.domain, service.health_check_endpoint
+    )
+}
+
+/// Whether the response body reports the version, as `version` field of
+/// JSON body or as the whole body.
+fn body_reports_version(body: &str, version: &str) -> bool {
+    if body.trim() == version {
+        return true;
+    }
+    serde_json::from_str::<serde_json::Value>(body)
+        .ok()
+        .and_then(|json| json.get(VERSION_FIELD).cloned())
+        .is_some_and(|reported| reported.as_str() == Some(version))
+}
+
+/// Wait until health check of the service answers with 2xx and reports
+/// exactly the version, in `X-Version` header, `version` field of JSON body
+/// or as the whole body.
+pub fn wait_healthy(
+    env: &Env,
+    service: &Service,
+    version: &str,
+    timeout_minutes: u64,
+) -> ServiceResult<()> {
+    if !service.has_web_interface {
+        println!(
+            "{}Service {} has no web interface, skipping health check",
+            output_prefix(),
+            service.name
+        );
+        return Ok(());
+    }
+
+    let url = get_health_check_url(env, service);
+    let client = Client::builder()
+        .timeout(Duration::from_secs(REQUEST_TIMEOUT_SECS))
+        .build()
+        .map_err(|err| ServiceError::DeploymentError(err.to_string()))?;
+    let prefix = output_prefix();
+    println!(
+        "{}Waiting for {} to report version {}",
+        prefix, url, version
+    );
+
+    let started = Instant::now();
+    let mut last_state = String::new();
+    while started.elapsed() < Duration::from_secs(timeout_minutes * 60) {
+        let state = match client.get(&url).send() {
+            Ok(resp) if resp.status().is_success() => {
+                let header_version = resp
+                    .headers()
+                    .get(VERSION_HEADER)
+                    .and_then(|v| v.to_str().ok())
+                    .map(|v| v.trim().to_string());
+                let reports_version = header_version.as_deref() == Some(version)
+                    || resp
+                        .text()
+                        .is_ok_and(|body| body_reports_version(&body, version));
+                if reports_version {
+                    println!(
+                        "{}Service {} is healthy after {}s",
+                        prefix,
+                        service.name,
+                        started.elapsed().as_secs()
+                    );
+                    return Ok(());
+                }
+                "healthy, but does not report the new version yet".to_string()
+            }
+            Ok(resp) => format!("responded with {}", resp.status()),
+            Err(err) => format!("not reachable: {}", err),
+        };
+        if state != last_state {
+            println!("{}Service {} {}", prefix, service.name, state);
+            last_state = state;
+        }
+        sleep(Duration::from_secs(HEALTH_CHECK_INTERVAL_SECS));
+    }
+
+    Err(ServiceError::DeploymentError(format!(
+        "Health check {} did not pass within {} minutes, last state: {}",
+        url, timeout_minutes, last_state
+    )))
+}
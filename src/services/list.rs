@@ -1,12 +1,17 @@
 use super::types::ServiceError;
 use super::utils::get_services;
 use crate::api_client::ApiClient;
-use crate::schemas::Project;
+use crate::output::{print_structured, OutputFormat};
+use crate::schemas::{Project, Service};
 use crate::utils::{add_row_to_output_table, get_output_table};
 
-pub fn list_services(api_client: &ApiClient, project: Project) {
+pub fn list_services(api_client: &ApiClient, project: Project, output: OutputFormat) {
     match get_services(api_client, &project, None) {
         Ok(services) => {
+            if output.is_structured() {
+                print_structured(output, &services);
+                return;
+            }
             if services.is_empty() {
                 println!("Project has no services.");
                 return;
@@ -65,12 +70,20 @@ pub fn list_services(api_client: &ApiClient, project: Project) {
                 add_row_to_output_table(&mut table, vec!["ECR Repo", ecr_repo_name]);
                 add_row_to_output_table(&mut table, vec!["AWS Region", region]);
                 add_row_to_output_table(&mut table, vec!["AWS Account", account_id]);
+                if output.is_wide() {
+                    add_row_to_output_table(&mut table, vec!["Slug", service.slug.as_str()]);
+                    add_row_to_output_table(&mut table, vec!["ECR Repo URL", &ecr_repo_url]);
+                }
 
                 table.printstd();
             }
         }
 
         Err(ServiceError::ServicesNotFound(_err)) => {
+            if output.is_structured() {
+                print_structured(output, &Vec::<Service>::new());
+                return;
+            }
             println!("Project {} has no services yet.", project.name);
             return;
         }
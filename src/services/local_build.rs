@@ -0,0 +1,120 @@
+use std::io::{BufRead, BufReader, Write};
+use std::process::{Command, Stdio};
+use std::thread;
+
+use super::types::{ServiceError, ServiceResult};
+use crate::api_client::ApiClient;
+use crate::schemas::Service;
+use crate::utils::output_prefix;
+
+const DOCKER: &str = "docker";
+
+/// Build the image of the service with local docker, tag it with the version
+/// and push it to the service's ECR repository.
+pub fn build_locally(
+    api_client: &ApiClient,
+    service: &Service,
+    ecr_repo_uri: &str,
+    version: &str,
+    build_args: Option<Vec<String>>,
+) -> ServiceResult<()> {
+    let image = format!("{}:{}", ecr_repo_uri, version);
+
+    let mut args = vec![
+        "build".to_string(),
+        "-f".to_string(),
+        service.default_dockerfile_path.clone(),
+        "-t".to_string(),
+        image.clone(),
+    ];
+    if let Some(target) = service
+        .default_dockerfile_target
+        .as_ref()
+        .filter(|t| !t.is_empty())
+    {
+        args.push("--target".to_string());
+        args.push(target.clone());
+    }
+    for arg in build_args.unwrap_or_default() {
+        args.push("--build-arg".to_string());
+        args.push(arg);
+    }
+    args.push(".".to_string());
+
+    println!("{}Building image {}", output_prefix(), image);
+    run_docker(&args)?;
+
+    let credentials = api_client.get_registry_credentials(&service.slug)?;
+    docker_login(
+        &credentials.registry,
+        &credentials.username,
+        &credentials.password,
+    )?;
+
+    println!("{}Pushing image {}", output_prefix(), image);
+    run_docker(&["push".to_string(), image])
+}
+
+/// Run docker, printing its output prefixed with output prefix of the
+/// current thread.
+fn run_docker(args: &[String]) -> ServiceResult<()> {
+    debug!("{} {:?}", DOCKER, args);
+    let mut child = Command::new(DOCKER)
+        .args(args)
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?;
+    let prefix = output_prefix();
+    let stderr = child.stderr.take();
+    let stderr_prefix = prefix.clone();
+    let stderr_thread = thread::spawn(move || {
+        if let Some(stderr) = stderr {
+            for line in BufReader::new(stderr).lines().map_while(Result::ok) {
+                eprintln!("{}{}", stderr_prefix, line);
+            }
+        }
+    });
+    if let Some(stdout) = child.stdout.take() {
+        for line in BufReader::new(stdout).lines().map_while(Result::ok) {
+            println!("{}{}", prefix, line);
+        }
+    }
+    if stderr_thread.join().is_err() {
+        debug!("Failed to forward docker output");
+    }
+    let status = child.wait()?;
+    if !status.success() {
+        return Err(ServiceError::DeploymentError(format!(
+            "docker {} failed",
+            args[0]
+        )));
+    }
+    Ok(())
+}
+
+/// Login to the registry, password is passed over stdin so it does not show
+/// up in the process list.
+fn docker_login(registry: &str, username: &str, password: &str) -> ServiceResult<()> {
+    debug!("Logging in to registry {}", registry);
+    let mut child = Command::new(DOCKER)
+        .args([
+            "login",
+            "--username",
+            username,
+            "--password-stdin",
+            registry,
+        ])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .spawn()?;
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin.write_all(password.as_bytes())?;
+    }
+    if !child.wait()?.success() {
+        return Err(ServiceError::DeploymentError(format!(
+            "Failed to login to registry {}",
+            registry
+        )));
+    }
+    Ok(())
+}
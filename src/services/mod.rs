@@ -1,16 +1,29 @@
 mod add_db;
 mod create;
 mod deploy;
+mod deployments;
+mod git;
+mod health;
 mod list;
+mod local_build;
+mod package;
+mod progress;
+mod ssh;
 mod statics_bucket;
 mod types;
 mod update;
+mod upload;
 mod utils;
+mod version;
 
 pub use self::add_db::*;
 pub use self::create::*;
 pub use self::deploy::*;
+pub use self::deployments::*;
 pub use self::list::*;
+pub use self::package::package;
+pub use self::ssh::{get_session, SshOptions};
 pub use self::statics_bucket::*;
 pub use self::update::*;
 pub use self::utils::*;
+pub use self::version::VersionStrategy;
@@ -0,0 +1,221 @@
+use std::fs;
+use std::fs::File;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::process::exit;
+
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use ignore::gitignore::{Gitignore, GitignoreBuilder};
+use ignore::overrides::OverrideBuilder;
+use ignore::WalkBuilder;
+
+use super::progress::ProgressReader;
+use super::types::{ServiceError, ServiceResult};
+use crate::schemas::Service;
+use crate::utils::{add_row_to_output_table, get_output_table};
+
+/// Directory of the build context inside the tarball.
+pub const TARBALL_ROOT: &str = "build";
+const CHILISEED_IGNORE: &str = ".chiliseedignore";
+const DOCKER_IGNORE: &str = ".dockerignore";
+
+/// Never packaged, regardless of ignore files.
+const ALWAYS_IGNORED: [&str; 4] = [".git/", "*.pem", "_build/", "build_*.tar.gz"];
+
+/// Files of the service build context, relative to `root`.
+/// Files are selected with git's ignore rules: nested `.gitignore` files,
+/// `.git/info/exclude` and the global excludes file. On top of those
+/// `.chiliseedignore` files, using the same syntax, and `.dockerignore` in
+/// `root` are applied.
+pub fn get_build_files(root: &Path) -> ServiceResult<Vec<PathBuf>> {
+    let mut overrides = OverrideBuilder::new(root);
+    for pattern in ALWAYS_IGNORED.iter() {
+        overrides.add(&format!("!{}", pattern))?;
+    }
+    let docker_ignore = get_docker_ignore(root)?;
+
+    let walker = WalkBuilder::new(root)
+        .hidden(false)
+        .follow_links(true)
+        .require_git(false)
+        .overrides(overrides.build()?)
+        .add_custom_ignore_filename(CHILISEED_IGNORE)
+        .build();
+
+    let mut files = Vec::new();
+    for entry in walker {
+        let entry = entry?;
+        if !entry.file_type().is_some_and(|t| t.is_file()) {
+            continue;
+        }
+        let path = entry.path().strip_prefix(root).map_err(|err| {
+            ServiceError::DeploymentError(format!("{}: {}", entry.path().display(), err))
+        })?;
+        if docker_ignore
+            .matched_path_or_any_parents(path, false)
+            .is_ignore()
+        {
+            debug!("Ignoring by {}: {}", DOCKER_IGNORE, path.display());
+            continue;
+        }
+        files.push(path.to_path_buf());
+    }
+    files.sort();
+    Ok(files)
+}
+
+/// `.dockerignore` patterns are relative to the build context root, unlike
+/// gitignore patterns without a slash, which match at any level.
+fn get_docker_ignore(root: &Path) -> ServiceResult<Gitignore> {
+    let mut builder = GitignoreBuilder::new(root);
+    let path = root.join(DOCKER_IGNORE);
+    if path.is_file() {
+        for line in fs::read_to_string(&path)?.lines() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let (negation, pattern) = match line.strip_prefix('!') {
+                Some(p) => ("!", p),
+                None => ("", line),
+            };
+            let pattern = pattern.trim_start_matches("./").trim_start_matches('/');
+            builder.add_line(Some(path.clone()), &format!("{}/{}", negation, pattern))?;
+        }
+    }
+    Ok(builder.build()?)
+}
+
+/// Write gzipped tarball of the files, relative to `root`, into the writer.
+pub fn write_build_tarball<W: Write>(
+    root: &Path,
+    files: &[PathBuf],
+    writer: W,
+) -> ServiceResult<W> {
+    let entries: Vec<(PathBuf, PathBuf)> = files
+        .iter()
+        .map(|path| (root.join(path), Path::new(TARBALL_ROOT).join(path)))
+        .collect();
+    write_tarball(&entries, writer, &mut |_| {})
+}
+
+/// Write gzipped tarball of `(source path, name in tarball)` entries into the
+/// writer. `on_read` is called with amount of every read from the files.
+pub fn write_tarball<W: Write>(
+    entries: &[(PathBuf, PathBuf)],
+    writer: W,
+    on_read: &mut dyn FnMut(u64),
+) -> ServiceResult<W> {
+    let encoder = GzEncoder::new(writer, Compression::default());
+    let mut tar = tar::Builder::new(encoder);
+    for (path, name) in entries {
+        let file = File::open(path)?;
+        let mut header = tar::Header::new_gnu();
+        header.set_metadata(&file.metadata()?);
+        let reader = ProgressReader {
+            inner: file,
+            on_read: &mut *on_read,
+        };
+        tar.append_data(&mut header, name, reader)?;
+    }
+    Ok(tar.into_inner()?.finish()?)
+}
+
+/// Writer counting bytes written through it.
+struct CountingWriter<W: Write> {
+    inner: W,
+    count: u64,
+}
+
+impl<W: Write> Write for CountingWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let written = self.inner.write(buf)?;
+        self.count += written as u64;
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+fn format_size(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["B", "KB", "MB", "GB"];
+    let mut size = bytes as f64;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{} {}", bytes, UNITS[unit])
+    } else {
+        format!("{:.1} {}", size, UNITS[unit])
+    }
+}
+
+/// Show what would be uploaded to build the service, without deploying.
+/// With `out` the tarball is also saved to that file.
+pub fn package(services: &[Service], list: bool, out: Option<String>) {
+    if let Err(err) = print_package(services, list, out) {
+        eprintln!("Error: {}", err);
+        exit(1);
+    }
+}
+
+fn print_package(services: &[Service], list: bool, out: Option<String>) -> ServiceResult<()> {
+    let root = Path::new(".");
+    let files = get_build_files(root)?;
+
+    let mut total_size = 0;
+    let mut table = get_output_table();
+    for path in files.iter() {
+        let size = fs::metadata(root.join(path))?.len();
+        total_size += size;
+        if list {
+            add_row_to_output_table(
+                &mut table,
+                vec![&format_size(size), &path.display().to_string()],
+            );
+        }
+    }
+    if list {
+        table.printstd();
+        println!();
+    }
+
+    for service in services.iter() {
+        let dockerfile = Path::new(&service.default_dockerfile_path);
+        if !files.iter().any(|path| path == dockerfile) {
+            eprintln!(
+                "Warning: dockerfile {} of service {} is not included",
+                service.default_dockerfile_path, service.name
+            );
+        }
+    }
+
+    let compressed_size = match out {
+        Some(ref out_path) => {
+            write_build_tarball(root, &files, File::create(out_path)?)?;
+            fs::metadata(out_path)?.len()
+        }
+        None => {
+            let writer = CountingWriter {
+                inner: io::sink(),
+                count: 0,
+            };
+            write_build_tarball(root, &files, writer)?.count
+        }
+    };
+
+    let names: Vec<&str> = services.iter().map(|s| s.name.as_str()).collect();
+    println!("Services: {}", names.join(", "));
+    println!("Files: {}", files.len());
+    println!("Total size: {}", format_size(total_size));
+    println!("Compressed size: {}", format_size(compressed_size));
+    if let Some(out_path) = out {
+        println!("Saved to {}", out_path);
+    }
+    Ok(())
+}
@@ -0,0 +1,146 @@
+use std::io::{self, IsTerminal, Read};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread::sleep;
+use std::time::{Duration, Instant};
+
+use indicatif::{HumanBytes, MultiProgress, ProgressBar, ProgressStyle};
+
+use crate::utils::output_prefix;
+
+/// How often progress is reported when stdout is not a terminal.
+const REPORT_INTERVAL: Duration = Duration::from_secs(10);
+/// How often the drawing thread checks for new progress bars.
+const DRAW_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Progress bars of uploads running in parallel. Bars are drawn together by
+/// `draw` running on its own thread, so they do not overwrite each other.
+#[derive(Default)]
+pub struct UploadBars {
+    multi: MultiProgress,
+    done: AtomicBool,
+}
+
+impl UploadBars {
+    pub fn new() -> UploadBars {
+        UploadBars::default()
+    }
+
+    /// Draw progress bars until `finish` is called. Bars can be added while
+    /// drawing, every bar is finished when its upload progress is dropped.
+    pub fn draw(&self) {
+        while !self.done.load(Ordering::Relaxed) {
+            if let Err(err) = self.multi.join() {
+                debug!("Failed to draw progress: {}", err);
+            }
+            sleep(DRAW_POLL_INTERVAL);
+        }
+    }
+
+    pub fn finish(&self) {
+        self.done.store(true, Ordering::Relaxed);
+    }
+}
+
+/// Progress of an upload. Shown as a progress bar in terminal, otherwise
+/// reported with a log line every few seconds. Bar and lines are prefixed
+/// with output prefix of the current thread.
+pub struct UploadProgress {
+    bar: Option<ProgressBar>,
+    prefix: String,
+    total: u64,
+    sent: u64,
+    started: Instant,
+    last_report: Instant,
+}
+
+impl UploadProgress {
+    pub fn new(total: u64, bars: &UploadBars) -> UploadProgress {
+        let prefix = output_prefix();
+        let bar = if io::stdout().is_terminal() {
+            let bar = bars.multi.add(ProgressBar::new(total));
+            bar.set_style(
+                ProgressStyle::default_bar()
+                    .template("{prefix}{bar:40} {bytes}/{total_bytes} {bytes_per_sec} ETA {eta}"),
+            );
+            bar.set_prefix(&prefix);
+            Some(bar)
+        } else {
+            None
+        };
+        let now = Instant::now();
+        UploadProgress {
+            bar,
+            prefix,
+            total,
+            sent: 0,
+            started: now,
+            last_report: now,
+        }
+    }
+
+    pub fn inc(&mut self, bytes: u64) {
+        self.sent += bytes;
+        match self.bar {
+            Some(ref bar) => bar.inc(bytes),
+            None if self.last_report.elapsed() >= REPORT_INTERVAL => {
+                self.last_report = Instant::now();
+                self.report();
+            }
+            None => {}
+        }
+    }
+
+    pub fn finish(&self) {
+        match self.bar {
+            Some(ref bar) => bar.finish(),
+            None => self.report(),
+        }
+    }
+
+    fn report(&self) {
+        let elapsed = self.started.elapsed().as_secs_f64().max(0.001);
+        let rate = self.sent as f64 / elapsed;
+        let eta = if rate > 0.0 {
+            format!(
+                "{}s",
+                (self.total.saturating_sub(self.sent) as f64 / rate).round()
+            )
+        } else {
+            "-".to_string()
+        };
+        println!(
+            "{}Uploaded {} of {} ({}/s, ETA {})",
+            self.prefix,
+            HumanBytes(self.sent),
+            HumanBytes(self.total),
+            HumanBytes(rate as u64),
+            eta
+        );
+    }
+}
+
+/// Failed uploads leave their bar unfinished, which would keep the drawing
+/// thread waiting for it.
+impl Drop for UploadProgress {
+    fn drop(&mut self) {
+        if let Some(ref bar) = self.bar {
+            if !bar.is_finished() {
+                bar.abandon();
+            }
+        }
+    }
+}
+
+/// Reader calling `on_read` with amount of every read.
+pub struct ProgressReader<'a, R: Read> {
+    pub inner: R,
+    pub on_read: &'a mut dyn FnMut(u64),
+}
+
+impl<'a, R: Read> Read for ProgressReader<'a, R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let read = self.inner.read(buf)?;
+        (self.on_read)(read as u64);
+        Ok(read)
+    }
+}
@@ -0,0 +1,178 @@
+use std::env;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::net::TcpStream;
+#[cfg(unix)]
+use std::os::unix::fs::OpenOptionsExt;
+use std::path::{Path, PathBuf};
+use std::process;
+
+use ssh2::{CheckResult, HashType, KnownHostFileKind, Session};
+
+use super::types::{ServiceError, ServiceResult};
+use crate::schemas::Worker;
+use crate::utils::output_prefix;
+
+const SSH_PORT: u16 = 22;
+const FINGERPRINT_PREFIX: &str = "SHA256:";
+
+/// How to connect to workers.
+#[derive(Debug, Default)]
+pub struct SshOptions {
+    /// Authenticate with keys of the local ssh-agent
+    pub use_agent: bool,
+    /// OpenSSH known_hosts file to verify and record worker host keys
+    pub known_hosts: Option<PathBuf>,
+}
+
+/// Private key written to a file readable only by the current user, removed
+/// when dropped.
+struct PrivateKeyFile {
+    path: PathBuf,
+}
+
+impl PrivateKeyFile {
+    fn create(key_name: &str, key: &str) -> ServiceResult<PrivateKeyFile> {
+        let dir = dirs::runtime_dir().unwrap_or_else(env::temp_dir);
+        let path = dir.join(format!("chiliseed-{}-{}.pem", key_name, process::id()));
+        let key_file = PrivateKeyFile { path };
+        let mut options = OpenOptions::new();
+        options.write(true).create_new(true);
+        #[cfg(unix)]
+        options.mode(0o600);
+        let mut file = options.open(&key_file.path)?;
+        file.write_all(key.as_bytes())?;
+        Ok(key_file)
+    }
+
+    fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl Drop for PrivateKeyFile {
+    fn drop(&mut self) {
+        if let Err(err) = fs::remove_file(&self.path) {
+            eprintln!(
+                "{}Failed to remove private key {}: {}",
+                output_prefix(),
+                self.path.display(),
+                err
+            );
+        }
+    }
+}
+
+/// Open SSH session to the worker. Worker's key is used from memory, unless
+/// ssh-agent use is requested, then keys of the local ssh-agent are used.
+/// Fails if host key of the worker can not be verified.
+pub fn get_session(worker: &Worker, user: &str, options: &SshOptions) -> ServiceResult<Session> {
+    let tcp = TcpStream::connect(format!("{}:{}", worker.public_ip, SSH_PORT))?;
+    let mut sess = Session::new()?;
+    sess.set_tcp_stream(tcp);
+    sess.handshake()?;
+    verify_host_key(&sess, worker, options.known_hosts.as_deref())?;
+    if options.use_agent {
+        sess.userauth_agent(user)?;
+    } else {
+        authenticate_with_key(&sess, user, worker)?;
+    }
+    Ok(sess)
+}
+
+fn get_fingerprint(sess: &Session) -> ServiceResult<String> {
+    let hash = sess.host_key_hash(HashType::Sha256).ok_or_else(|| {
+        ServiceError::DeploymentError("Worker did not provide a host key".to_string())
+    })?;
+    Ok(format!(
+        "{}{}",
+        FINGERPRINT_PREFIX,
+        base64::encode_config(hash, base64::STANDARD_NO_PAD)
+    ))
+}
+
+/// Host key is verified with the fingerprint provided by the API and, when
+/// given, with the known hosts file. Keys verified by the API are added to
+/// the known hosts file.
+fn verify_host_key(
+    sess: &Session,
+    worker: &Worker,
+    known_hosts_path: Option<&Path>,
+) -> ServiceResult<()> {
+    let fingerprint = get_fingerprint(sess)?;
+    debug!("Worker {} host key: {}", worker.public_ip, fingerprint);
+
+    let verified_by_api = match worker.host_key_fingerprint {
+        Some(ref expected) => {
+            let expected = expected.trim().trim_end_matches('=');
+            if expected != fingerprint
+                && format!("{}{}", FINGERPRINT_PREFIX, expected) != fingerprint
+            {
+                return Err(ServiceError::DeploymentError(format!(
+                    "Host key of worker {} does not match! Expected {}, got {}. Someone may be intercepting the connection.",
+                    worker.public_ip, expected, fingerprint
+                )));
+            }
+            true
+        }
+        None => false,
+    };
+
+    let known_hosts_path = match known_hosts_path {
+        Some(path) => path,
+        None if verified_by_api => return Ok(()),
+        None => {
+            return Err(ServiceError::DeploymentError(format!(
+                "Host key {} of worker {} can not be verified, API did not provide its fingerprint. Use --known-hosts to verify it with a known hosts file.",
+                fingerprint, worker.public_ip
+            )))
+        }
+    };
+
+    let (key, key_type) = sess.host_key().ok_or_else(|| {
+        ServiceError::DeploymentError("Worker did not provide a host key".to_string())
+    })?;
+    let mut known_hosts = sess.known_hosts()?;
+    if known_hosts_path.is_file() {
+        known_hosts.read_file(known_hosts_path, KnownHostFileKind::OpenSSH)?;
+    }
+    match known_hosts.check_port(&worker.public_ip, SSH_PORT, key) {
+        CheckResult::Match => Ok(()),
+        CheckResult::NotFound if verified_by_api => {
+            known_hosts.add(&worker.public_ip, key, &worker.slug, key_type.into())?;
+            known_hosts.write_file(known_hosts_path, KnownHostFileKind::OpenSSH)?;
+            debug!(
+                "Added worker {} to {}",
+                worker.public_ip,
+                known_hosts_path.display()
+            );
+            Ok(())
+        }
+        CheckResult::NotFound => Err(ServiceError::DeploymentError(format!(
+            "Worker {} with host key {} is not in {}",
+            worker.public_ip,
+            fingerprint,
+            known_hosts_path.display()
+        ))),
+        CheckResult::Mismatch => Err(ServiceError::DeploymentError(format!(
+            "Host key of worker {} does not match the one in {}! Someone may be intercepting the connection.",
+            worker.public_ip,
+            known_hosts_path.display()
+        ))),
+        CheckResult::Failure => Err(ServiceError::DeploymentError(format!(
+            "Failed to check host key of worker {} in {}",
+            worker.public_ip,
+            known_hosts_path.display()
+        ))),
+    }
+}
+
+fn authenticate_with_key(sess: &Session, user: &str, worker: &Worker) -> ServiceResult<()> {
+    match sess.userauth_pubkey_memory(user, None, &worker.ssh_key, None) {
+        Ok(()) => return Ok(()),
+        Err(err) => debug!("Failed to use key from memory, using key file: {}", err),
+    }
+    let key_file = PrivateKeyFile::create(&worker.ssh_key_name, &worker.ssh_key)?;
+    sess.userauth_pubkey_file(user, None, key_file.path(), None)?;
+    Ok(())
+}
@@ -3,9 +3,9 @@ use crate::env_vars;
 use crate::schemas::{Project, Service};
 use crate::utils::await_exec_result;
 
-const STATICS_BUCKET_KEY_NAME: &str = "AWS_STATICS_STORAGE_BUCKET_NAME";
+pub const STATICS_BUCKET_KEY_NAME: &str = "AWS_STATICS_STORAGE_BUCKET_NAME";
 
-pub fn add_statics(api_client: &ApiClient, service: Service, project: Project) {
+pub fn add_statics(api_client: &ApiClient, service: Service, project: Project) -> bool {
     let (run_slug, bucket_slug) = match api_client.create_statics_bucket(
         &service.slug,
         &CreateStaticsBucketRequest {
@@ -16,7 +16,7 @@ pub fn add_statics(api_client: &ApiClient, service: Service, project: Project) {
         Err(err) => {
             debug!("Error: {}", err);
             eprintln!("Server error. Please try again later");
-            return;
+            return false;
         }
     };
     println!("Adding statics bucket to service: {}", service.name);
@@ -28,11 +28,11 @@ pub fn add_statics(api_client: &ApiClient, service: Service, project: Project) {
             Ok(resource) => resource,
             Err(_) => {
                 eprintln!("Server error. Please try again later.");
-                return;
+                return false;
             }
         };
         debug!("new resource: {:?}", bucket);
-        env_vars::create(
+        let is_created = env_vars::create(
             api_client,
             service,
             STATICS_BUCKET_KEY_NAME,
@@ -43,7 +43,9 @@ pub fn add_statics(api_client: &ApiClient, service: Service, project: Project) {
             STATICS_BUCKET_KEY_NAME
         );
         println!("Redeploy {} services to see this variable.", project.name);
+        return is_created;
     }
+    false
 }
 
 pub fn remove_statics(api_client: &ApiClient, service: Service) {
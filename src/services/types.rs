@@ -12,6 +12,7 @@ pub enum ServiceError {
     ServicesNotFound(String),
     APIError(ApiClientError),
     DeploymentError(String),
+    ArgumentError(String),
 }
 
 pub type ServiceResult<T> = Result<T, ServiceError>;
@@ -20,7 +21,14 @@ impl Error for ServiceError {}
 
 impl fmt::Display for ServiceError {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "{}", self.to_string())
+        match *self {
+            ServiceError::EnvError(ref err) => err.fmt(f),
+            ServiceError::ProjectError(ref err) => err.fmt(f),
+            ServiceError::ServicesNotFound(ref cause) => write!(f, "{}", cause),
+            ServiceError::APIError(ref err) => err.fmt(f),
+            ServiceError::DeploymentError(ref cause) => write!(f, "{}", cause),
+            ServiceError::ArgumentError(ref cause) => write!(f, "{}", cause),
+        }
     }
 }
 
@@ -48,8 +56,8 @@ impl From<io::Error> for ServiceError {
     }
 }
 
-impl From<globset::Error> for ServiceError {
-    fn from(err: globset::Error) -> ServiceError {
+impl From<ignore::Error> for ServiceError {
+    fn from(err: ignore::Error) -> ServiceError {
         ServiceError::DeploymentError(err.to_string())
     }
 }
@@ -1,53 +1,122 @@
+use std::process::exit;
+
+use super::types::ServiceResult;
 use crate::api_client::ApiClient;
+use crate::commands::ServiceParams;
 use crate::schemas::{Project, Service};
-use crate::services::{ask_for_value, ask_yes_no};
+use crate::services::{ask_for_value, ask_yes_no, parse_port};
 use crate::utils::await_exec_result;
 
-pub fn update(api_client: &ApiClient, project: Project, service: Service) {
-    let name = ask_for_value(
+pub fn update(api_client: &ApiClient, project: Project, service: Service, params: ServiceParams) {
+    let service = match get_updated_service(service, params) {
+        Ok(s) => s,
+        Err(err) => {
+            eprintln!("Error: {}", err);
+            exit(1);
+        }
+    };
+
+    let run_slug = match api_client.update_service(&service, &project.slug) {
+        Ok(resp) => resp.log,
+        Err(_) => return,
+    };
+
+    println!("Updating service");
+    await_exec_result(api_client, &run_slug, None);
+}
+
+/// Value provided in command line, otherwise ask user with current value as
+/// default. In non interactive mode current value is kept.
+fn value_or_current(
+    value: Option<String>,
+    non_interactive: bool,
+    question: String,
+    current: String,
+) -> String {
+    match value {
+        Some(v) => v,
+        None if non_interactive => current,
+        None => ask_for_value(question, current),
+    }
+}
+
+fn get_updated_service(service: Service, params: ServiceParams) -> ServiceResult<Service> {
+    let non_interactive = params.non_interactive;
+    let has_web_params = params.has_web_params();
+
+    let name = value_or_current(
+        params.name,
+        non_interactive,
         format!("Your service name (current: {}): ", service.name),
         service.name.clone(),
     );
 
-    let has_web_interface = ask_yes_no(
-        format!(
-            "Does your service need web interface? (current: {}) [y/n]: ",
-            service.has_web_interface
-        ),
-        service.has_web_interface.clone(),
-    );
+    let has_web_interface = if params.no_web {
+        false
+    } else if has_web_params {
+        true
+    } else if non_interactive {
+        service.has_web_interface
+    } else {
+        ask_yes_no(
+            format!(
+                "Does your service need web interface? (current: {}) [y/n]: ",
+                service.has_web_interface
+            ),
+            service.has_web_interface,
+        )
+    };
 
-    let mut subdomain = "".to_string();
-    let mut container_port = "".to_string();
-    let mut alb_port_http = "".to_string();
-    let mut alb_port_https = "".to_string();
-    let mut health_check_endpoint = "".to_string();
+    let mut subdomain = service.subdomain.clone();
+    let mut container_port = service.container_port;
+    let mut alb_port_http = service.alb_port_http;
+    let mut alb_port_https = service.alb_port_https;
+    let mut health_check_endpoint = service.health_check_endpoint.clone();
 
     if has_web_interface {
-        subdomain = ask_for_value(
+        subdomain = value_or_current(
+            params.subdomain,
+            non_interactive,
             format!("Your service subdomain (current: {}): ", service.subdomain),
             service.subdomain.clone(),
         );
 
-        container_port = ask_for_value(
-            format!(
-                "On what port will your container listen (current: {}): ",
-                service.container_port
+        container_port = parse_port(
+            &value_or_current(
+                params.container_port.map(|p| p.to_string()),
+                non_interactive,
+                format!(
+                    "On what port will your container listen (current: {}): ",
+                    service.container_port
+                ),
+                format!("{}", service.container_port),
             ),
-            format!("{}", service.container_port),
-        );
+            "--container-port",
+        )?;
 
-        alb_port_http = ask_for_value(
-            format!("On what port do you want the load balancer to listen for HTTP traffic (current: {}): ", service.alb_port_http),
-            format!("{}", service.alb_port_http)
-        );
+        alb_port_http = parse_port(
+            &value_or_current(
+                params.alb_http_port.map(|p| p.to_string()),
+                non_interactive,
+                format!("On what port do you want the load balancer to listen for HTTP traffic (current: {}): ", service.alb_port_http),
+                format!("{}", service.alb_port_http),
+            ),
+            "--alb-http-port",
+        )?;
 
-        alb_port_https = ask_for_value(
-            format!("On what port do you want the load balancer to listen for HTTPS traffic (current: {}): ", service.alb_port_https),
-            format!("{}", service.alb_port_https),
-        );
+        alb_port_https = parse_port(
+            &value_or_current(
+                params.alb_https_port.map(|p| p.to_string()),
+                non_interactive,
+                format!("On what port do you want the load balancer to listen for HTTPS traffic (current: {}): ", service.alb_port_https),
+                format!("{}", service.alb_port_https),
+            ),
+            "--alb-https-port",
+        )?;
 
-        health_check_endpoint = ask_for_value(
+        health_check_endpoint = value_or_current(
+            params.health_check,
+            non_interactive,
             format!(
                 "What is your health check endpoint (current: {}): ",
                 service.health_check_endpoint
@@ -56,7 +125,9 @@ pub fn update(api_client: &ApiClient, project: Project, service: Service) {
         );
     }
 
-    let default_dockerfile_path = ask_for_value(
+    let default_dockerfile_path = value_or_current(
+        params.dockerfile,
+        non_interactive,
         format!(
             "Path to service's dockerfile, relative to project root (current: {}): ",
             service.default_dockerfile_path
@@ -64,34 +135,31 @@ pub fn update(api_client: &ApiClient, project: Project, service: Service) {
         service.default_dockerfile_path.clone(),
     );
 
-    let dockerfile_target = service.default_dockerfile_target.unwrap_or("".to_string());
-    let default_dockerfile_target = ask_for_value(
+    let dockerfile_target = service
+        .default_dockerfile_target
+        .clone()
+        .unwrap_or_default();
+    let default_dockerfile_target = value_or_current(
+        params.target,
+        non_interactive,
         format!(
             "Optional specific dockerfile target to build (current: {}): ",
-            dockerfile_target.clone()
+            dockerfile_target
         ),
-        dockerfile_target,
+        dockerfile_target.clone(),
     );
 
-    let service = Service {
+    Ok(Service {
         slug: service.slug.clone(),
         name,
         has_web_interface,
         default_dockerfile_path,
-        default_dockerfile_target: Some(default_dockerfile_target),
+        default_dockerfile_target: Some(default_dockerfile_target).filter(|t| !t.is_empty()),
         subdomain,
-        container_port: container_port.parse::<u32>().unwrap(),
-        alb_port_http: alb_port_http.parse::<u32>().unwrap(),
-        alb_port_https: alb_port_https.parse::<u32>().unwrap(),
+        container_port,
+        alb_port_http,
+        alb_port_https,
         health_check_endpoint,
         ecr_repo_url: service.ecr_repo_url.clone(),
-    };
-
-    let run_slug = match api_client.update_service(&service, &project.slug) {
-        Ok(resp) => resp.log,
-        Err(_) => return,
-    };
-
-    println!("Updating service");
-    await_exec_result(api_client, &run_slug, None);
+    })
 }
@@ -0,0 +1,212 @@
+use std::collections::HashSet;
+use std::fs::{self, File};
+use std::io::{self, BufWriter, Read, Write};
+#[cfg(unix)]
+use std::os::unix::fs::PermissionsExt;
+use std::path::{Path, PathBuf};
+
+use sha2::{Digest, Sha256};
+use ssh2::{Channel, Session};
+
+use super::package::{get_build_files, write_tarball, TARBALL_ROOT};
+use super::progress::{UploadBars, UploadProgress};
+use super::types::{ServiceError, ServiceResult};
+use crate::utils::output_prefix;
+
+const UPLOAD_BUFFER_SIZE: usize = 1024 * 1024;
+/// Blobs not used by any build for this many days are removed from workers.
+const BLOB_RETENTION_DAYS: u32 = 7;
+
+/// File of the build context, identified by hash of its content.
+pub struct BuildFile {
+    pub(super) path: PathBuf,
+    pub(super) hash: String,
+    pub(super) mode: u32,
+}
+
+fn hash_file(path: &Path) -> ServiceResult<String> {
+    let mut hasher = Sha256::new();
+    io::copy(&mut File::open(path)?, &mut hasher)?;
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+/// Permission bits of the file to recreate on the worker.
+#[cfg(unix)]
+fn get_mode(metadata: &fs::Metadata) -> u32 {
+    metadata.permissions().mode() & 0o777
+}
+
+/// Without unix permissions files are uploaded as readable by everyone and
+/// writable by the owner.
+#[cfg(not(unix))]
+fn get_mode(metadata: &fs::Metadata) -> u32 {
+    if metadata.permissions().readonly() {
+        0o444
+    } else {
+        0o644
+    }
+}
+
+/// Build files of `root` with hashes of their content.
+pub fn get_build_manifest(root: &Path) -> ServiceResult<Vec<BuildFile>> {
+    let mut manifest = Vec::new();
+    for path in get_build_files(root)? {
+        let path_str = path.to_str().unwrap_or_default();
+        if path_str.is_empty() || path_str.contains(['\t', '\n']) {
+            return Err(ServiceError::DeploymentError(format!(
+                "Unsupported file name in build: {:?}",
+                path
+            )));
+        }
+        let full_path = root.join(&path);
+        manifest.push(BuildFile {
+            hash: hash_file(&full_path)?,
+            mode: get_mode(&fs::metadata(&full_path)?),
+            path,
+        });
+    }
+    Ok(manifest)
+}
+
+/// Execute command on the worker feeding it `input` on stdin, returns stdout
+/// of the command.
+fn exec_with_input<F>(ssh_conn: &Session, cmd: &str, input: F) -> ServiceResult<String>
+where
+    F: FnOnce(&mut BufWriter<&mut Channel>) -> ServiceResult<()>,
+{
+    debug!("[remote]: {}", cmd);
+    let mut channel = ssh_conn.channel_session()?;
+    channel.exec(cmd)?;
+
+    let mut writer = BufWriter::with_capacity(UPLOAD_BUFFER_SIZE, &mut channel);
+    input(&mut writer)?;
+    writer.flush()?;
+    drop(writer);
+    channel.send_eof()?;
+
+    let mut stdout = String::new();
+    channel.read_to_string(&mut stdout)?;
+    let mut stderr = String::new();
+    channel.stderr().read_to_string(&mut stderr)?;
+    channel.wait_close()?;
+    if channel.exit_status()? != 0 {
+        return Err(ServiceError::DeploymentError(format!(
+            "Command failed on worker: {}: {}",
+            cmd,
+            stderr.trim()
+        )));
+    }
+    Ok(stdout)
+}
+
+/// Upload build context into `deployment_dir` on the worker.
+/// Files are stored on the worker by hash of their content, so only files
+/// the worker has not seen in previous builds are uploaded. Uploaded files
+/// are moved among the stored ones only once their content matches the hash,
+/// an interrupted upload never leaves a truncated file to be reused.
+pub fn upload_build_context(
+    ssh_conn: &Session,
+    deployment_dir: &str,
+    manifest: &[BuildFile],
+    bars: &UploadBars,
+) -> ServiceResult<()> {
+    let root = Path::new(".");
+    let blobs_dir = format!("{}-blobs", deployment_dir);
+
+    let hashes: HashSet<&str> = manifest.iter().map(|f| f.hash.as_str()).collect();
+    let missing_output = exec_with_input(
+        ssh_conn,
+        &format!(
+            "mkdir -p {dir} && while read -r hash; do [ -f {dir}/$hash ] || echo $hash; done | sort",
+            dir = blobs_dir
+        ),
+        |writer| {
+            for hash in hashes.iter() {
+                writeln!(writer, "{}", hash)?;
+            }
+            Ok(())
+        },
+    )?;
+    let missing: HashSet<&str> = missing_output.lines().map(|l| l.trim()).collect();
+
+    let mut uploaded = HashSet::new();
+    let entries: Vec<(PathBuf, PathBuf)> = manifest
+        .iter()
+        .filter(|f| missing.contains(f.hash.as_str()) && uploaded.insert(f.hash.as_str()))
+        .map(|f| (root.join(&f.path), PathBuf::from(&f.hash)))
+        .collect();
+    println!(
+        "{}Uploading {} of {} files to build worker",
+        output_prefix(),
+        entries.len(),
+        manifest.len()
+    );
+    if !entries.is_empty() {
+        let mut total = 0;
+        for (path, _) in entries.iter() {
+            total += fs::metadata(path)?.len();
+        }
+        let mut progress = UploadProgress::new(total, bars);
+        exec_with_input(
+            ssh_conn,
+            &format!(
+                "tmp=$(mktemp -d {blobs}/.upload.XXXXXX) && trap 'rm -rf \"$tmp\"' EXIT && \
+                 tar -xzf - -C \"$tmp\" && \
+                 for blob in \"$tmp\"/*; do hash=$(basename \"$blob\"); \
+                 [ \"$(sha256sum < \"$blob\" | cut -d' ' -f1)\" = \"$hash\" ] || {{ echo \"Upload of $hash is corrupted\" >&2; exit 1; }}; \
+                 mv \"$blob\" {blobs}/$hash || exit 1; \
+                 done",
+                blobs = blobs_dir
+            ),
+            |writer| {
+                write_tarball(&entries, writer, &mut |bytes| progress.inc(bytes))?;
+                Ok(())
+            },
+        )?;
+        progress.finish();
+    }
+
+    let build_dir = format!("{}/{}", deployment_dir, TARBALL_ROOT);
+    exec_with_input(
+        ssh_conn,
+        &format!(
+            "rm -rf {deployment} && mkdir -p {build} && cd {build} && \
+             while IFS=\"$(printf '\\t')\" read -r hash mode path; do \
+             mkdir -p \"$(dirname \"$path\")\" && cp {blobs}/$hash \"$path\" && chmod $mode \"$path\" && \
+             touch -c {blobs}/$hash || exit 1; \
+             done",
+            deployment = deployment_dir,
+            build = build_dir,
+            blobs = blobs_dir
+        ),
+        |writer| {
+            for file in manifest.iter() {
+                writeln!(
+                    writer,
+                    "{}\t{:o}\t{}",
+                    file.hash,
+                    file.mode,
+                    file.path.display()
+                )?;
+            }
+            Ok(())
+        },
+    )?;
+    prune_blobs(ssh_conn, &blobs_dir)
+}
+
+/// Remove stored files not used by builds in the last `BLOB_RETENTION_DAYS`
+/// days and uploads left over by killed connections. Files used by a build
+/// are touched when the build context is assembled.
+fn prune_blobs(ssh_conn: &Session, blobs_dir: &str) -> ServiceResult<()> {
+    exec_with_input(
+        ssh_conn,
+        &format!(
+            "find {blobs} -mindepth 1 -maxdepth 1 -mtime +{days} -exec rm -rf {{}} +",
+            blobs = blobs_dir,
+            days = BLOB_RETENTION_DAYS
+        ),
+        |_| Ok(()),
+    )?;
+    Ok(())
+}
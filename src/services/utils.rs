@@ -61,3 +61,31 @@ pub(crate) fn ask_yes_no(question: String, default: bool) -> bool {
         vec!["y", "ye", "yes", "yep"].contains(&yes.to_lowercase().as_str())
     }
 }
+
+/// Use value provided in command line, otherwise ask user for it.
+/// In non interactive mode a missing value is an error.
+pub(crate) fn value_or_ask<F>(
+    value: Option<String>,
+    flag: &str,
+    non_interactive: bool,
+    ask: F,
+) -> ServiceResult<String>
+where
+    F: FnOnce() -> String,
+{
+    match value {
+        Some(v) => Ok(v),
+        None if non_interactive => Err(ServiceError::ArgumentError(format!(
+            "Missing required argument {}",
+            flag
+        ))),
+        None => Ok(ask()),
+    }
+}
+
+/// Parse port number entered by user.
+pub(crate) fn parse_port(value: &str, flag: &str) -> ServiceResult<u32> {
+    value.trim().parse::<u32>().map_err(|_err| {
+        ServiceError::ArgumentError(format!("Invalid port number for {}: {}", flag, value))
+    })
+}
@@ -0,0 +1,336 @@
+use std::fmt;
+use std::str::FromStr;
+
+use chrono::Utc;
+use sha2::{Digest, Sha256};
+
+use super::git::{
+    get_description, get_revision, get_uncommitted_changes, is_git_checkout, warn_unpushed,
+    Revision,
+};
+use super::types::{ServiceError, ServiceResult};
+use super::upload::BuildFile;
+
+const GIT_SHA: &str = "git-sha";
+const GIT_DESCRIBE: &str = "git-describe";
+const TIMESTAMP: &str = "timestamp";
+const CONTENT_HASH: &str = "content-hash";
+
+const TIMESTAMP_FORMAT: &str = "%Y%m%d%H%M%S";
+const CONTENT_HASH_LEN: usize = 12;
+const BUILD_ARGS_HASH_LEN: usize = 8;
+/// Maximum length of an image tag in ECR.
+const MAX_TAG_LEN: usize = 128;
+
+/// Source of the version new builds are tagged with.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum VersionStrategy {
+    /// Short sha of the current commit
+    GitSha,
+    /// `git describe --tags --always` of the current commit
+    GitDescribe,
+    /// UTC time of the deploy
+    Timestamp,
+    /// Hash of the build context files and build arguments
+    ContentHash,
+}
+
+impl VersionStrategy {
+    pub fn variants() -> [&'static str; 4] {
+        [GIT_SHA, GIT_DESCRIBE, TIMESTAMP, CONTENT_HASH]
+    }
+
+    fn uses_git(self) -> bool {
+        self == VersionStrategy::GitSha || self == VersionStrategy::GitDescribe
+    }
+}
+
+impl FromStr for VersionStrategy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            GIT_SHA => Ok(VersionStrategy::GitSha),
+            GIT_DESCRIBE => Ok(VersionStrategy::GitDescribe),
+            TIMESTAMP => Ok(VersionStrategy::Timestamp),
+            CONTENT_HASH => Ok(VersionStrategy::ContentHash),
+            _ => Err(format!("Unsupported version strategy: {}", s)),
+        }
+    }
+}
+
+impl fmt::Display for VersionStrategy {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            VersionStrategy::GitSha => write!(f, "{}", GIT_SHA),
+            VersionStrategy::GitDescribe => write!(f, "{}", GIT_DESCRIBE),
+            VersionStrategy::Timestamp => write!(f, "{}", TIMESTAMP),
+            VersionStrategy::ContentHash => write!(f, "{}", CONTENT_HASH),
+        }
+    }
+}
+
+/// Version of the image to deploy.
+#[derive(Debug)]
+pub struct BuildVersion {
+    pub version: String,
+    /// Git revision the image is built from, recorded with the deployment
+    pub revision: Option<Revision>,
+    /// `None` for already built image deployed with `--version`
+    pub strategy: Option<VersionStrategy>,
+}
+
+impl BuildVersion {
+    /// Existing image with content hash version was built from the same
+    /// build context, it does not need to be built again.
+    pub fn is_content_addressed(&self) -> bool {
+        self.strategy == Some(VersionStrategy::ContentHash)
+    }
+}
+
+/// Strategy to version the new build with. Without a strategy git sha is
+/// used, outside of git checkout content hash.
+pub fn resolve_strategy(strategy: Option<VersionStrategy>) -> ServiceResult<VersionStrategy> {
+    let in_git = is_git_checkout();
+    let strategy = match strategy {
+        Some(strategy) => strategy,
+        None if in_git => VersionStrategy::GitSha,
+        None => {
+            println!("Not a git checkout, versioning by {}", CONTENT_HASH);
+            VersionStrategy::ContentHash
+        }
+    };
+    if strategy.uses_git() && !in_git {
+        return Err(ServiceError::ArgumentError(format!(
+            "Version strategy {} requires a git checkout",
+            strategy
+        )));
+    }
+    Ok(strategy)
+}
+
+/// Version to tag the new build with, and git revision it is built from when
+/// deploying from a git checkout. `manifest` is only used by content hash.
+/// Git versions of builds with build arguments get `-args-<hash>` suffix,
+/// builds of the same commit with different arguments must not share a tag.
+pub fn get_build_version(
+    strategy: VersionStrategy,
+    allow_dirty: bool,
+    manifest: &[BuildFile],
+    build_args: Option<&[String]>,
+) -> ServiceResult<BuildVersion> {
+    let revision = if strategy.uses_git() {
+        Some(get_revision()?)
+    } else {
+        get_optional_revision(is_git_checkout())
+    };
+    let version = match strategy {
+        VersionStrategy::GitSha => {
+            let short_sha = revision
+                .as_ref()
+                .map(|r| r.short_sha.clone())
+                .ok_or_else(|| {
+                    ServiceError::DeploymentError("Git revision not found".to_string())
+                })?;
+            with_build_args_suffix(short_sha, build_args)
+        }
+        VersionStrategy::GitDescribe => with_build_args_suffix(get_description()?, build_args),
+        VersionStrategy::Timestamp => Utc::now().format(TIMESTAMP_FORMAT).to_string(),
+        VersionStrategy::ContentHash => hash_build_context(manifest, build_args),
+    };
+
+    // uncommitted changes are checked with every strategy, the recorded
+    // revision would not match the deployed code otherwise
+    let version = match revision {
+        Some(ref revision) => {
+            let version = revision.with_dirty_suffix(&version);
+            check_dirty(revision, &version, allow_dirty)?;
+            warn_unpushed(revision);
+            version
+        }
+        None => version,
+    };
+    validate_version(&version)?;
+    Ok(BuildVersion {
+        version,
+        revision,
+        strategy: Some(strategy),
+    })
+}
+
+fn get_optional_revision(in_git: bool) -> Option<Revision> {
+    if !in_git {
+        return None;
+    }
+    get_revision()
+        .map_err(|err| debug!("Failed to get git revision: {}", err))
+        .ok()
+}
+
+/// Uncommitted changes would be packaged too, so they are refused unless
+/// `allow_dirty`, which deploys them as a `-dirty-<hash>` version, marking
+/// that the recorded commit is not all of the deployed code.
+fn check_dirty(revision: &Revision, version: &str, allow_dirty: bool) -> ServiceResult<()> {
+    if revision.dirty_hash.is_none() {
+        return Ok(());
+    }
+    if !allow_dirty {
+        return Err(ServiceError::DeploymentError(format!(
+            "Working tree has uncommitted changes:\n{}\nCommit them, or pass --allow-dirty to deploy them as version {}",
+            get_uncommitted_changes()?,
+            version
+        )));
+    }
+    eprintln!(
+        "Warning: deploying uncommitted changes as version {}",
+        version
+    );
+    Ok(())
+}
+
+/// Hash of paths, modes and contents of the build files and of the build
+/// arguments, the same build context always gets the same version.
+fn hash_build_context(manifest: &[BuildFile], build_args: Option<&[String]>) -> String {
+    let mut hasher = Sha256::new();
+    for file in manifest.iter() {
+        hasher.update(format!(
+            "{}\0{:o}\0{}\n",
+            file.path.display(),
+            file.mode,
+            file.hash
+        ));
+    }
+    hash_build_args(&mut hasher, build_args);
+    let hash = format!("{:x}", hasher.finalize());
+    hash[..CONTENT_HASH_LEN].to_string()
+}
+
+fn hash_build_args(hasher: &mut Sha256, build_args: Option<&[String]>) {
+    for arg in build_args.unwrap_or_default() {
+        hasher.update(format!("--build-arg\0{}\n", arg));
+    }
+}
+
+fn with_build_args_suffix(version: String, build_args: Option<&[String]>) -> String {
+    if build_args.unwrap_or_default().is_empty() {
+        return version;
+    }
+    let mut hasher = Sha256::new();
+    hash_build_args(&mut hasher, build_args);
+    let hash = format!("{:x}", hasher.finalize());
+    format!("{}-args-{}", version, &hash[..BUILD_ARGS_HASH_LEN])
+}
+
+/// Versions are image tags, which in ECR consist of up to 128 letters,
+/// digits, underscores, periods and dashes, not starting with period or dash.
+pub fn validate_version(version: &str) -> ServiceResult<()> {
+    let is_valid_char = |c: char| c.is_ascii_alphanumeric() || c == '_' || c == '.' || c == '-';
+    let error = |cause: &str| {
+        Err(ServiceError::ArgumentError(format!(
+            "Invalid version {:?}: {}",
+            version, cause
+        )))
+    };
+    if version.is_empty() {
+        return error("version is empty");
+    }
+    if version.len() > MAX_TAG_LEN {
+        return error(&format!("longer than {} characters", MAX_TAG_LEN));
+    }
+    if version.starts_with(['.', '-']) {
+        return error("must not start with period or dash");
+    }
+    if let Some(c) = version.chars().find(|c| !is_valid_char(*c)) {
+        return error(&format!(
+            "character {:?} is not allowed, use letters, digits, '_', '.' and '-'",
+            c
+        ));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::*;
+
+    fn build_file(path: &str, hash: &str) -> BuildFile {
+        BuildFile {
+            path: PathBuf::from(path),
+            hash: hash.to_string(),
+            mode: 0o644,
+        }
+    }
+
+    #[test]
+    fn validate_version_accepts_tags() {
+        assert!(validate_version("1a2b3c4").is_ok());
+        assert!(validate_version("v1.2.0-3-g1a2b3c4_dirty").is_ok());
+        assert!(validate_version(&"a".repeat(MAX_TAG_LEN)).is_ok());
+    }
+
+    #[test]
+    fn validate_version_rejects_invalid_tags() {
+        assert!(validate_version("").is_err());
+        assert!(validate_version(&"a".repeat(MAX_TAG_LEN + 1)).is_err());
+        assert!(validate_version(".1").is_err());
+        assert!(validate_version("-1").is_err());
+        assert!(validate_version("feature/x").is_err());
+        assert!(validate_version("v 1").is_err());
+    }
+
+    #[test]
+    fn strategy_round_trips() {
+        for name in VersionStrategy::variants().iter() {
+            let strategy: VersionStrategy = name.parse().unwrap();
+            assert_eq!(strategy.to_string(), *name);
+        }
+        assert_eq!(
+            "Git-SHA".parse::<VersionStrategy>(),
+            Ok(VersionStrategy::GitSha)
+        );
+        assert!("svn".parse::<VersionStrategy>().is_err());
+    }
+
+    #[test]
+    fn content_hash_is_stable() {
+        let manifest = vec![build_file("Dockerfile", "aa"), build_file("app.py", "bb")];
+        let args = vec!["ENV=prod".to_string()];
+        let hash = hash_build_context(&manifest, Some(&args));
+        assert_eq!(hash.len(), CONTENT_HASH_LEN);
+        assert_eq!(hash, hash_build_context(&manifest, Some(&args)));
+        assert!(validate_version(&hash).is_ok());
+    }
+
+    #[test]
+    fn content_hash_changes_with_build_context() {
+        let manifest = vec![build_file("Dockerfile", "aa")];
+        let args = vec!["ENV=prod".to_string()];
+        let other_args = vec!["ENV=dev".to_string()];
+        let hash = hash_build_context(&manifest, Some(&args));
+        assert_ne!(hash, hash_build_context(&manifest, Some(&other_args)));
+        assert_ne!(hash, hash_build_context(&manifest, None));
+        assert_ne!(
+            hash,
+            hash_build_context(&[build_file("Dockerfile", "ab")], Some(&args))
+        );
+    }
+
+    #[test]
+    fn build_args_suffix() {
+        assert_eq!(
+            with_build_args_suffix("1a2b3c4".to_string(), None),
+            "1a2b3c4"
+        );
+        let args = vec!["ENV=prod".to_string()];
+        let version = with_build_args_suffix("1a2b3c4".to_string(), Some(&args));
+        assert!(version.starts_with("1a2b3c4-args-"));
+        assert_eq!(version.len(), "1a2b3c4-args-".len() + BUILD_ARGS_HASH_LEN);
+        let other_args = vec!["ENV=dev".to_string()];
+        assert_ne!(
+            version,
+            with_build_args_suffix("1a2b3c4".to_string(), Some(&other_args))
+        );
+    }
+}
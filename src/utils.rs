@@ -1,76 +1,227 @@
-use std::process::{exit, Command};
+use std::cell::RefCell;
+use std::process::exit;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Mutex;
 use std::thread::sleep;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
-use crate::api_client::ApiClient;
+use crate::api_client::{ApiClient, ApiClientError, ExecLogLinesResponse};
+use crate::config::{ActiveProfile, Config};
+use crate::context;
+use crate::context::Context;
 use crate::db::get_db;
 use crate::environments::get_env;
 use crate::projects::{get_env_name, get_project, get_project_name};
 use crate::schemas::{Env, Project, Resource, Service};
-use crate::services::{get_service, get_service_name};
+use crate::services::{get_service, get_service_name, get_services};
 use prettytable::{format, Cell, Row, Table};
 
 const WAIT_TIME_SECS: u64 = 10;
+const LOG_POLL_SECS: u64 = 2;
 const WAIT_SERVER_TIMEOUT_MINUTES: u64 = 30;
+const MAX_STATUS_RETRIES: u32 = 6;
+const MAX_RETRY_WAIT_SECS: u64 = 60;
+
+/// Waiting behaviour set by global command line options.
+#[derive(Debug, Default)]
+pub struct WaitOptions {
+    /// Do not stream execution logs
+    pub quiet: bool,
+    /// Overrides the timeout of every wait
+    pub timeout_minutes: Option<u64>,
+    /// Seconds between status checks
+    pub poll_interval_secs: Option<u64>,
+}
+
+static QUIET: AtomicBool = AtomicBool::new(false);
+/// Zero means not set.
+static TIMEOUT_MINUTES: AtomicU64 = AtomicU64::new(0);
+/// Zero means not set.
+static POLL_INTERVAL_SECS: AtomicU64 = AtomicU64::new(0);
+/// Executions being awaited, reported when user interrupts the wait.
+static CURRENT_RUNS: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+thread_local! {
+    /// Prefix of output lines printed by the current thread.
+    static OUTPUT_PREFIX: RefCell<String> = const { RefCell::new(String::new()) };
+}
+
+/// Prefix output lines of the current thread with the name, so output of
+/// tasks running in parallel can be told apart.
+pub fn set_output_prefix(name: &str) {
+    OUTPUT_PREFIX.with(|prefix| *prefix.borrow_mut() = format!("[{}] ", name));
+}
+
+/// Prefix of output lines of the current thread, empty unless set.
+pub fn output_prefix() -> String {
+    OUTPUT_PREFIX.with(|prefix| prefix.borrow().clone())
+}
+
+pub fn set_wait_options(options: WaitOptions) {
+    QUIET.store(options.quiet, Ordering::Relaxed);
+    TIMEOUT_MINUTES.store(options.timeout_minutes.unwrap_or(0), Ordering::Relaxed);
+    POLL_INTERVAL_SECS.store(options.poll_interval_secs.unwrap_or(0), Ordering::Relaxed);
+}
+
+fn get_option(value: &AtomicU64) -> Option<u64> {
+    Some(value.load(Ordering::Relaxed)).filter(|v| *v > 0)
+}
+
+fn add_current_run(run_slug: &str) {
+    if let Ok(mut current) = CURRENT_RUNS.lock() {
+        current.push(run_slug.to_string());
+    }
+}
+
+fn remove_current_run(run_slug: &str) {
+    if let Ok(mut current) = CURRENT_RUNS.lock() {
+        current.retain(|slug| slug != run_slug);
+    }
+}
+
+/// On Ctrl-C tell user how to get back to the execution being awaited, it
+/// keeps running on the server.
+pub fn handle_interrupts() {
+    let result = ctrlc::set_handler(|| {
+        let current = CURRENT_RUNS.lock().map(|c| c.clone()).unwrap_or_default();
+        if !current.is_empty() {
+            eprintln!();
+        }
+        for run_slug in current.iter() {
+            eprintln!("Interrupted. Execution {} keeps running.", run_slug);
+            eprintln!("Resume waiting with: chiliseed exec-log wait {}", run_slug);
+        }
+        exit(130);
+    });
+    if let Err(err) = result {
+        debug!("Failed to set Ctrl-C handler: {}", err);
+    }
+}
+
+/// Print log lines of the run added since `offset`, returns offset of the
+/// following lines. Failing to fetch logs is not fatal for the run itself.
+pub fn print_new_log_lines(api_client: &ApiClient, run_slug: &str, offset: u64) -> u64 {
+    match api_client.get_exec_log_lines(run_slug, offset) {
+        Ok(ExecLogLinesResponse { lines, offset }) => {
+            let prefix = output_prefix();
+            for line in lines.iter() {
+                println!("{}{}", prefix, line);
+            }
+            offset
+        }
+        Err(err) => {
+            debug!("Failed to fetch execution logs: {}", err);
+            offset
+        }
+    }
+}
+
+/// Print all log lines of the run since `offset`, fetching pages of them
+/// until there are no more, returns offset of the following lines.
+pub fn print_all_log_lines(api_client: &ApiClient, run_slug: &str, mut offset: u64) -> u64 {
+    loop {
+        let new_offset = print_new_log_lines(api_client, run_slug, offset);
+        if new_offset <= offset {
+            return offset;
+        }
+        offset = new_offset;
+    }
+}
 
 /// Await infra executors for a specific run.
 /// Providing `timeout_minutes` will wait for that amount of minutes, otherwise
-/// default value is using of 30 minutes.
+/// default value is using of 30 minutes. Global `--timeout` overrides both.
+/// Execution logs are printed as they arrive, unless `--quiet` was set.
 pub fn await_exec_result(
     api_client: &ApiClient,
     run_slug: &str,
     timeout_minutes: Option<u64>,
 ) -> bool {
-    let timeout_minutes = timeout_minutes.unwrap_or(WAIT_SERVER_TIMEOUT_MINUTES);
-    let mut waited = 0;
+    add_current_run(run_slug);
+    let is_success = wait_for_run(api_client, run_slug, timeout_minutes);
+    remove_current_run(run_slug);
+    is_success
+}
+
+fn wait_for_run(api_client: &ApiClient, run_slug: &str, timeout_minutes: Option<u64>) -> bool {
+    let timeout_minutes = get_option(&TIMEOUT_MINUTES)
+        .or(timeout_minutes)
+        .unwrap_or(WAIT_SERVER_TIMEOUT_MINUTES);
+    let quiet = QUIET.load(Ordering::Relaxed);
+    let poll_secs = get_option(&POLL_INTERVAL_SECS).unwrap_or(if quiet {
+        WAIT_TIME_SECS
+    } else {
+        LOG_POLL_SECS
+    });
+    let prefix = output_prefix();
+    let started = Instant::now();
+    let mut offset = 0;
+    let mut retries = 0;
+    println!("{}Execution: {}", prefix, run_slug);
     loop {
+        let waited = started.elapsed().as_secs();
         if waited >= timeout_minutes * 60 {
-            eprintln!("TIMING OUT after 30 minutes. Please contact support for help");
+            eprintln!(
+                "{}TIMING OUT after {} minutes. Execution {} may still be running, check it with: chiliseed exec-log show {}",
+                prefix, timeout_minutes, run_slug, run_slug
+            );
             return false;
         }
 
-        println!("Checking create status");
+        if quiet {
+            println!("{}Checking create status", prefix);
+        } else {
+            offset = print_new_log_lines(api_client, run_slug, offset);
+        }
 
         match api_client.get_exec_log(&run_slug) {
             Ok(exec_log) => {
                 debug!("{:?}", exec_log);
+                retries = 0;
 
                 if let Some(success) = exec_log.is_success {
+                    if !quiet {
+                        print_all_log_lines(api_client, run_slug, offset);
+                    }
                     if success {
-                        println!("Infra is ready after {}s", waited);
+                        println!("{}Infra is ready after {}s", prefix, waited);
                         return true;
                     }
-                    println!("ERROR creating infra after {}s", waited);
+                    println!("{}ERROR creating infra after {}s", prefix, waited);
                     return false;
                 }
 
-                sleep(Duration::from_secs(WAIT_TIME_SECS));
-                waited += WAIT_TIME_SECS;
-                println!("Still creating [{}s]", waited);
-                continue;
+                sleep(Duration::from_secs(poll_secs));
+                if quiet {
+                    println!(
+                        "{}Still creating [{}s]",
+                        prefix,
+                        started.elapsed().as_secs()
+                    );
+                }
             }
-            Err(_err) => {
-                eprintln!("Error checking status");
+            Err(ApiClientError::Unauthorized(cause)) => {
+                eprintln!("{}Error checking status: {}", prefix, cause);
                 return false;
             }
+            Err(err) => {
+                retries += 1;
+                if retries > MAX_STATUS_RETRIES {
+                    eprintln!("{}Error checking status: {}", prefix, err);
+                    eprintln!(
+                        "{}Resume waiting with: chiliseed exec-log wait {}",
+                        prefix, run_slug
+                    );
+                    return false;
+                }
+                let backoff = (1 << retries).min(MAX_RETRY_WAIT_SECS);
+                debug!("Error checking status: {}, retrying in {}s", err, backoff);
+                sleep(Duration::from_secs(backoff));
+            }
         }
     }
 }
 
-/// Wrapper for executing any commands in command line and get the output for
-/// further processing
-pub fn exec_command_with_output(
-    cmd: &str,
-    args: Vec<&str>,
-) -> Result<(bool, Vec<u8>), &'static str> {
-    debug!("{} {:?}", cmd, args);
-    let output = Command::new(cmd)
-        .args(&args)
-        .output()
-        .expect("Failed to execute command");
-    Ok((output.status.success(), output.stdout))
-}
-
 /// Construct pretty table object, to be used for outputting data structures
 /// in table format.
 pub fn get_output_table() -> Table {
@@ -96,6 +247,33 @@ pub fn add_row_to_output_table(table: &mut Table, values: Vec<&str>) {
     table.add_row(Row::new(cells));
 }
 
+pub fn get_profile_or_exit(profile_name: Option<String>) -> ActiveProfile {
+    let config = match Config::load() {
+        Ok(config) => config,
+        Err(err) => {
+            eprintln!("Error: {}", err);
+            exit(1);
+        }
+    };
+    match config.get_profile(profile_name) {
+        Ok(profile) => profile,
+        Err(err) => {
+            eprintln!("Error: {}", err);
+            exit(1);
+        }
+    }
+}
+
+pub fn get_context_or_exit(profile: &ActiveProfile) -> Context {
+    match context::load(profile) {
+        Ok(context) => context,
+        Err(err) => {
+            eprintln!("Error: {}", err);
+            exit(1);
+        }
+    }
+}
+
 pub fn get_environment_or_exit(api_client: &ApiClient, environment_name: Option<String>) -> Env {
     let env_name = get_env_name(environment_name);
     match get_env(api_client, &env_name) {
@@ -131,6 +309,32 @@ pub fn get_service_or_exit(
     get_service(&api_client, &project, &service_name)
 }
 
+/// Services to act on: all services of the project with `all`, services with
+/// the names or, without any names, the default service.
+pub fn get_services_or_exit(
+    api_client: &ApiClient,
+    project: &Project,
+    service_names: Vec<String>,
+    all: bool,
+) -> Vec<Service> {
+    if all {
+        return match get_services(api_client, project, None) {
+            Ok(services) => services,
+            Err(err) => {
+                eprintln!("Error: {}", err);
+                exit(1);
+            }
+        };
+    }
+    if service_names.is_empty() {
+        return vec![get_service_or_exit(api_client, project, None)];
+    }
+    service_names
+        .iter()
+        .map(|name| get_service(api_client, project, name))
+        .collect()
+}
+
 pub fn get_resource_or_exit(
     api_client: &ApiClient,
     project: &Project,
@@ -0,0 +1,343 @@
+use std::env;
+use std::io::{self, Read, Write};
+#[cfg(unix)]
+use std::mem;
+use std::process::exit;
+use std::sync::mpsc;
+use std::thread;
+use std::time::Duration;
+
+use ssh2::{Channel, Session};
+
+use crate::api_client::ApiClient;
+use crate::output::{print_structured, OutputFormat};
+use crate::schemas::Worker;
+use crate::services::{ask_yes_no, get_session, SshOptions, BUILD_WORKER_USER};
+use crate::utils::{add_row_to_output_table, await_exec_result, get_output_table};
+
+const DEFAULT_TERM: &str = "xterm";
+const DEFAULT_TERMINAL_SIZE: (u32, u32) = (80, 24);
+const SHELL_POLL_MILLIS: u64 = 10;
+
+fn get_status(worker: &Worker) -> &'static str {
+    if worker.is_ready {
+        "ready"
+    } else {
+        "launching"
+    }
+}
+
+fn get_created_at(worker: &Worker) -> String {
+    worker
+        .created_at
+        .map(|t| t.to_rfc2822())
+        .unwrap_or_else(|| "-".to_string())
+}
+
+fn get_worker_or_exit(api_client: &ApiClient, slug: &str) -> Worker {
+    match api_client.get_worker_details(slug) {
+        Ok(worker) => worker,
+        Err(err) => {
+            eprintln!("Error getting worker: {}", err);
+            exit(1);
+        }
+    }
+}
+
+pub fn list_workers(api_client: &ApiClient, output: OutputFormat) {
+    let workers = match api_client.list_workers() {
+        Ok(workers) => workers,
+        Err(err) => {
+            eprintln!("Error getting workers: {}", err);
+            return;
+        }
+    };
+    if output.is_structured() {
+        print_structured(output, &workers);
+        return;
+    }
+    if workers.is_empty() {
+        println!("No build workers found.");
+        return;
+    }
+
+    let mut table = get_output_table();
+    let mut header = vec![
+        "Slug",
+        "Service",
+        "Version",
+        "Status",
+        "Public IP",
+        "Created at",
+    ];
+    if output.is_wide() {
+        header.push("Host key");
+    }
+    add_row_to_output_table(&mut table, header);
+    for worker in workers.iter() {
+        let created_at = get_created_at(worker);
+        let mut row = vec![
+            worker.slug.as_str(),
+            worker.service_name.as_deref().unwrap_or("-"),
+            worker.version.as_deref().unwrap_or("-"),
+            get_status(worker),
+            worker.public_ip.as_str(),
+            created_at.as_str(),
+        ];
+        if output.is_wide() {
+            row.push(worker.host_key_fingerprint.as_deref().unwrap_or("-"));
+        }
+        add_row_to_output_table(&mut table, row);
+    }
+    table.printstd();
+}
+
+pub fn describe_worker(api_client: &ApiClient, slug: &str, output: OutputFormat) {
+    let worker = get_worker_or_exit(api_client, slug);
+    if output.is_structured() {
+        print_structured(output, &worker);
+        return;
+    }
+
+    let created_at = get_created_at(&worker);
+    let mut table = get_output_table();
+    add_row_to_output_table(&mut table, vec!["Slug", worker.slug.as_str()]);
+    add_row_to_output_table(
+        &mut table,
+        vec!["Service", worker.service_name.as_deref().unwrap_or("-")],
+    );
+    add_row_to_output_table(
+        &mut table,
+        vec!["Version", worker.version.as_deref().unwrap_or("-")],
+    );
+    add_row_to_output_table(&mut table, vec!["Status", get_status(&worker)]);
+    add_row_to_output_table(&mut table, vec!["Public IP", worker.public_ip.as_str()]);
+    add_row_to_output_table(
+        &mut table,
+        vec!["SSH key name", worker.ssh_key_name.as_str()],
+    );
+    add_row_to_output_table(
+        &mut table,
+        vec![
+            "Host key",
+            worker.host_key_fingerprint.as_deref().unwrap_or("-"),
+        ],
+    );
+    add_row_to_output_table(&mut table, vec!["Created at", created_at.as_str()]);
+    table.printstd();
+}
+
+/// Terminate the worker, for example one left running by a crashed deploy.
+pub fn terminate_worker(api_client: &ApiClient, slug: &str, auto_approve: bool) {
+    let worker = get_worker_or_exit(api_client, slug);
+    if !auto_approve
+        && !ask_yes_no(
+            format!(
+                "Terminate build worker {} ({})? [y/n]: ",
+                worker.slug, worker.public_ip
+            ),
+            false,
+        )
+    {
+        println!("Terminate cancelled.");
+        return;
+    }
+
+    let run_log = match api_client.terminate_worker(&worker.slug) {
+        Ok(log) => log,
+        Err(err) => {
+            eprintln!("Error terminating worker: {}", err);
+            exit(1);
+        }
+    };
+    println!("Terminating build worker {}", worker.slug);
+    if !await_exec_result(api_client, &run_log.slug, None) {
+        exit(1);
+    }
+}
+
+/// Open interactive shell on the worker, exits with exit code of the shell.
+pub fn ssh_worker(api_client: &ApiClient, slug: &str, ssh_options: &SshOptions) {
+    let worker = get_worker_or_exit(api_client, slug);
+    if !worker.is_ready {
+        eprintln!("Error: worker {} is not ready yet", worker.slug);
+        exit(1);
+    }
+    let session = match get_session(&worker, BUILD_WORKER_USER, ssh_options) {
+        Ok(session) => session,
+        Err(err) => {
+            eprintln!("Error connecting to worker: {}", err);
+            exit(1);
+        }
+    };
+    match run_shell(&session) {
+        Ok(code) => exit(code),
+        Err(err) => {
+            eprintln!("Error: {}", err);
+            exit(1);
+        }
+    }
+}
+
+/// Local terminal in raw mode, so keys are passed to the remote shell as
+/// they are typed. Original mode is restored when dropped.
+#[cfg(unix)]
+struct RawTerminal {
+    original: libc::termios,
+}
+
+#[cfg(unix)]
+impl RawTerminal {
+    /// `None` when stdin is not a terminal.
+    fn enable() -> Option<RawTerminal> {
+        unsafe {
+            if libc::isatty(libc::STDIN_FILENO) == 0 {
+                return None;
+            }
+            let mut termios: libc::termios = mem::zeroed();
+            if libc::tcgetattr(libc::STDIN_FILENO, &mut termios) != 0 {
+                return None;
+            }
+            let original = termios;
+            libc::cfmakeraw(&mut termios);
+            if libc::tcsetattr(libc::STDIN_FILENO, libc::TCSANOW, &termios) != 0 {
+                return None;
+            }
+            Some(RawTerminal { original })
+        }
+    }
+}
+
+#[cfg(unix)]
+impl Drop for RawTerminal {
+    fn drop(&mut self) {
+        unsafe {
+            libc::tcsetattr(libc::STDIN_FILENO, libc::TCSANOW, &self.original);
+        }
+    }
+}
+
+/// Without raw mode input is sent to the shell line by line.
+#[cfg(not(unix))]
+struct RawTerminal;
+
+#[cfg(not(unix))]
+impl RawTerminal {
+    fn enable() -> Option<RawTerminal> {
+        None
+    }
+}
+
+/// Columns and rows of the local terminal.
+#[cfg(unix)]
+fn get_terminal_size() -> (u32, u32) {
+    unsafe {
+        let mut size: libc::winsize = mem::zeroed();
+        if libc::ioctl(libc::STDOUT_FILENO, libc::TIOCGWINSZ, &mut size) == 0 && size.ws_col > 0 {
+            (u32::from(size.ws_col), u32::from(size.ws_row))
+        } else {
+            DEFAULT_TERMINAL_SIZE
+        }
+    }
+}
+
+#[cfg(not(unix))]
+fn get_terminal_size() -> (u32, u32) {
+    DEFAULT_TERMINAL_SIZE
+}
+
+/// Retry a non blocking session operation until it does not block.
+fn retry_blocked<T, F>(mut op: F) -> io::Result<T>
+where
+    F: FnMut() -> io::Result<T>,
+{
+    loop {
+        match op() {
+            Err(err) if err.kind() == io::ErrorKind::WouldBlock => {
+                thread::sleep(Duration::from_millis(1))
+            }
+            result => return result,
+        }
+    }
+}
+
+fn write_to_channel(channel: &mut Channel, mut input: &[u8]) -> io::Result<()> {
+    while !input.is_empty() {
+        let written = retry_blocked(|| channel.write(input))?;
+        input = &input[written..];
+    }
+    Ok(())
+}
+
+/// Run login shell of the session, passing local stdin to it and its output
+/// to stdout until it exits. Returns exit code of the shell.
+/// When the local terminal can be switched to raw mode the shell runs on a
+/// pty following size of the local terminal, otherwise input is sent line
+/// by line to a shell without pty, which would echo the lines again.
+fn run_shell(session: &Session) -> io::Result<i32> {
+    let raw_terminal = RawTerminal::enable();
+    let mut channel = session.channel_session()?;
+    let mut size = get_terminal_size();
+    if raw_terminal.is_some() {
+        let term = env::var("TERM").unwrap_or_else(|_| DEFAULT_TERM.to_string());
+        channel.request_pty(&term, None, Some((size.0, size.1, 0, 0)))?;
+    }
+    channel.shell()?;
+
+    // stdin has no non blocking reads, it is read on its own thread, empty
+    // input means end of it
+    let (sender, receiver) = mpsc::channel::<Vec<u8>>();
+    thread::spawn(move || {
+        let mut buf = [0; 1024];
+        loop {
+            let n = io::stdin().read(&mut buf).unwrap_or(0);
+            if sender.send(buf[..n].to_vec()).is_err() || n == 0 {
+                break;
+            }
+        }
+    });
+
+    session.set_blocking(false);
+    let mut stdout = io::stdout();
+    let mut buf = [0; 4096];
+    while !channel.eof() {
+        let mut idle = true;
+        match channel.read(&mut buf) {
+            Ok(0) => {}
+            Ok(n) => {
+                stdout.write_all(&buf[..n])?;
+                stdout.flush()?;
+                idle = false;
+            }
+            Err(err) if err.kind() == io::ErrorKind::WouldBlock => {}
+            Err(err) => return Err(err),
+        }
+        while let Ok(input) = receiver.try_recv() {
+            if input.is_empty() {
+                retry_blocked(|| channel.send_eof().map_err(io::Error::from))?;
+            } else {
+                write_to_channel(&mut channel, &input)?;
+            }
+            idle = false;
+        }
+        if raw_terminal.is_some() {
+            let new_size = get_terminal_size();
+            if new_size != size {
+                size = new_size;
+                retry_blocked(|| {
+                    channel
+                        .request_pty_size(size.0, size.1, None, None)
+                        .map_err(io::Error::from)
+                })?;
+            }
+        }
+        if idle {
+            thread::sleep(Duration::from_millis(SHELL_POLL_MILLIS));
+        }
+    }
+    session.set_blocking(true);
+    drop(raw_terminal);
+
+    channel.wait_close()?;
+    Ok(channel.exit_status()?)
+}